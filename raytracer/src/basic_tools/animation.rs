@@ -0,0 +1,297 @@
+use super::super::hittable::{hittable_list::HittableList, tlas::TLAS, transform::Motion};
+use super::super::material::{metal::Material, subsurface::Subsurface};
+use super::{
+    camera::Camera,
+    matrix::{Matrix4, Pose, Quaternion},
+    vec3::{Color, Point, Vec3},
+};
+use std::sync::Arc;
+
+pub trait Lerp: Copy {
+    fn lerp(a: Self, b: Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(a: Self, b: Self, t: f64) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(a: Self, b: Self, t: f64) -> Self {
+        a + (b - a) * t
+    }
+}
+
+//keyframes of one value, linear in between and held before the first and after the last key
+#[derive(Clone)]
+pub struct Track<T>
+where
+    T: Lerp,
+{
+    pub keys: Vec<(f64, T)>, //sorted by time, in seconds
+}
+
+impl<T: Lerp> Track<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            keys: (vec![(0.0, value)]),
+        }
+    }
+
+    pub fn key(mut self, time: f64, value: T) -> Self {
+        self.keys.retain(|k| k.0 != time);
+        let index = self.keys.iter().filter(|k| k.0 < time).count();
+        self.keys.insert(index, (time, value));
+        self
+    }
+
+    pub fn at(&self, time: f64) -> T {
+        let first = self.keys[0];
+        if time <= first.0 {
+            return first.1;
+        }
+        for pair in self.keys.windows(2) {
+            let (t0, v0) = pair[0];
+            let (t1, v1) = pair[1];
+            if time < t1 {
+                return T::lerp(v0, v1, (time - t0) / (t1 - t0));
+            }
+        }
+        self.keys[self.keys.len() - 1].1
+    }
+}
+
+//translate * rotate * scale, the rotation as angles in degrees about x, then y, then z
+#[derive(Clone)]
+pub struct TransformTrack {
+    pub translation: Track<Vec3>,
+    pub rotation: Track<Vec3>,
+    pub scale: Track<Vec3>,
+}
+
+impl Default for TransformTrack {
+    fn default() -> Self {
+        Self {
+            translation: Track::new(Vec3::new(0.0, 0.0, 0.0)),
+            rotation: Track::new(Vec3::new(0.0, 0.0, 0.0)),
+            scale: Track::new(Vec3::new(1.0, 1.0, 1.0)),
+        }
+    }
+}
+
+impl TransformTrack {
+    pub fn pose(&self, time: f64) -> Pose {
+        Pose::new(
+            self.translation.at(time),
+            Quaternion::from_euler(self.rotation.at(time)),
+            self.scale.at(time),
+        )
+    }
+
+    pub fn matrix(&self, time: f64) -> Matrix4 {
+        self.pose(time).matrix()
+    }
+
+    //the pose blended across an open shutter for motion blur
+    pub fn motion(&self, time: f64, shutter: f64) -> Motion {
+        Motion::new(
+            self.pose(time),
+            self.pose(time + shutter),
+            time,
+            time + shutter,
+        )
+    }
+}
+
+//keyframed Camera::new arguments
+#[derive(Clone)]
+pub struct CameraPath {
+    pub lookfrom: Track<Point>,
+    pub lookat: Track<Point>,
+    pub vup: Vec3,
+    pub vfov: Track<f64>,
+    pub aspect_ratio: f64,
+    pub aperture: Track<f64>,
+    pub focus_dist: Track<f64>,
+}
+
+impl CameraPath {
+    pub fn new(lookfrom: Point, lookat: Point, vfov: f64, aspect_ratio: f64) -> Self {
+        Self {
+            lookfrom: (Track::new(lookfrom)),
+            lookat: (Track::new(lookat)),
+            vup: (Vec3::new(0.0, 1.0, 0.0)),
+            vfov: (Track::new(vfov)),
+            aspect_ratio: (aspect_ratio),
+            aperture: (Track::new(0.0)),
+            focus_dist: (Track::new(10.0)),
+        }
+    }
+
+    //the shutter stays open from time to time + shutter, the camera moves while it is
+    pub fn camera(&self, time: f64, shutter: f64) -> Camera {
+        Camera::new_moving(
+            [self.lookfrom.at(time), self.lookfrom.at(time + shutter)],
+            [self.lookat.at(time), self.lookat.at(time + shutter)],
+            self.vup,
+            self.vfov.at(time),
+            self.aspect_ratio,
+            self.aperture.at(time),
+            self.focus_dist.at(time),
+            time,
+            time + shutter,
+        )
+    }
+
+    pub fn whale() -> Self {
+        let aspect_ratio = 16.0 / 9.0;
+        let mut path = CameraPath::new(
+            Point::new(278.0, 278.0, -800.0),
+            Point::new(278.0, 278.0, 0.0),
+            40.0,
+            aspect_ratio,
+        );
+        path.lookfrom = path
+            .lookfrom
+            .key(1.0, Point::new(378.0, 320.0, -760.0))
+            .key(2.0, Point::new(178.0, 300.0, -780.0));
+        path.lookat = path.lookat.key(2.0, Point::new(300.0, 330.0, 0.0));
+        path
+    }
+}
+
+//frames start..=end at fps, frame 0 is at time 0
+#[derive(Clone, Copy)]
+pub struct Timeline {
+    pub start: usize,
+    pub end: usize,
+    pub fps: f64,
+    pub shutter_angle: f64, //degrees of the frame the shutter is open, 0 turns motion blur off
+}
+
+impl Timeline {
+    pub fn new(start: usize, end: usize, fps: f64) -> Self {
+        Self {
+            start: (start),
+            end: (end),
+            fps: (fps),
+            shutter_angle: 180.0,
+        }
+    }
+
+    pub fn time(&self, frame: usize) -> f64 {
+        frame as f64 / self.fps
+    }
+
+    pub fn shutter(&self) -> f64 {
+        self.shutter_angle / 360.0 / self.fps
+    }
+
+    pub fn frame_path(&self, dir: &str, frame: usize) -> String {
+        format!("{}/frame_{:04}.png", dir, frame)
+    }
+}
+
+pub type MaterialTrack = Arc<dyn Fn(f64) -> Arc<dyn Material> + Send + Sync>;
+
+//what changes for one instance of the top level structure
+#[derive(Clone)]
+pub struct InstanceTrack {
+    pub index: usize,
+    pub transform: Option<TransformTrack>,
+    pub material: Option<MaterialTrack>, //builds the override material for a time
+}
+
+//the moving part of a scene, everything else is built once and shared by all frames
+#[derive(Clone)]
+pub struct Animation {
+    pub timeline: Timeline,
+    pub camera: CameraPath,
+    pub instances: TLAS,
+    pub tracks: Vec<InstanceTrack>,
+}
+
+impl Animation {
+    pub fn new(timeline: Timeline, camera: CameraPath, instances: TLAS) -> Self {
+        Self {
+            timeline: (timeline),
+            camera: (camera),
+            instances: (instances),
+            tracks: Vec::new(),
+        }
+    }
+
+    pub fn animate_transform(&mut self, index: usize, transform: TransformTrack) {
+        self.track_mut(index).transform = Some(transform);
+    }
+
+    pub fn animate_material(&mut self, index: usize, material: MaterialTrack) {
+        self.track_mut(index).material = Some(material);
+    }
+
+    fn track_mut(&mut self, index: usize) -> &mut InstanceTrack {
+        let position = match self.tracks.iter().position(|t| t.index == index) {
+            Some(position) => position,
+            None => {
+                self.tracks.push(InstanceTrack {
+                    index,
+                    transform: None,
+                    material: None,
+                });
+                self.tracks.len() - 1
+            }
+        };
+        &mut self.tracks[position]
+    }
+
+    //poses the instances for the frame, only the top level structure is rebuilt
+    pub fn frame(&mut self, frame: usize) -> (Camera, TLAS) {
+        let time = self.timeline.time(frame);
+        let shutter = self.timeline.shutter();
+        for track in &self.tracks {
+            if let Some(transform) = &track.transform {
+                if shutter > 0.0 {
+                    self.instances
+                        .set_motion(track.index, transform.motion(time, shutter));
+                } else {
+                    self.instances
+                        .set_matrix(track.index, transform.matrix(time));
+                }
+            }
+            if let Some(material) = &track.material {
+                self.instances.instances[track.index].material = Some(material(time));
+            }
+        }
+        self.instances.rebuild();
+        let camera = self.camera.camera(time, shutter);
+        (camera, self.instances.clone())
+    }
+
+    //the whale swims forward and turns while its color shifts, two seconds at 24 fps
+    pub fn whale() -> Self {
+        let (instances, whale) = HittableList::whale_instances();
+        let mut animation =
+            Animation::new(Timeline::new(0, 47, 24.0), CameraPath::whale(), instances);
+        let swim = TransformTrack {
+            translation: Track::new(Vec3::new(300.0, 350.0, 400.0))
+                .key(1.0, Vec3::new(260.0, 380.0, 380.0))
+                .key(2.0, Vec3::new(200.0, 360.0, 350.0)),
+            rotation: Track::new(Vec3::new(0.0, 180.0, 0.0))
+                .key(1.0, Vec3::new(5.0, 195.0, 0.0))
+                .key(2.0, Vec3::new(0.0, 210.0, 0.0)),
+            ..TransformTrack::default()
+        };
+        animation.animate_transform(whale, swim);
+
+        let albedo = Track::new(Color::new(0.55, 0.75, 0.95)).key(2.0, Color::new(0.5, 0.9, 0.8));
+        animation.animate_material(
+            whale,
+            Arc::new(move |time| {
+                Arc::new(Subsurface::new_albedo(1.33, albedo.at(time), 15.0, 0.3))
+                    as Arc<dyn Material>
+            }),
+        );
+        animation
+    }
+}
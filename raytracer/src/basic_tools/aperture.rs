@@ -0,0 +1,131 @@
+use super::super::hittable::{hittable_origin::random_double, pdf::Distribution1D};
+use super::camera::degrees_to_radians;
+use super::vec3::Vec3;
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+//a grayscale mask for the lens opening, brighter pixels let more light through
+pub struct ApertureImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Distribution1D, //row by row from the top
+}
+
+impl ApertureImage {
+    pub fn new(filename: &str) -> Self {
+        let pathname = String::from("img/") + filename;
+        let image = image::open(Path::new(&pathname)).unwrap().to_luma8();
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let luma = image.pixels().map(|p| p[0] as f64 / 255.0).collect();
+        Self {
+            width: (width),
+            height: (height),
+            pixels: (Distribution1D::new(luma)),
+        }
+    }
+
+    //point in [-1,1]^2, the longer side of the image spans the lens diameter
+    pub fn sample(&self) -> (f64, f64) {
+        let mut pdf = 0.0;
+        let mut index = 0;
+        let x = self
+            .pixels
+            .sample_continuous(random_double(), &mut pdf, &mut index);
+        let column = (x * self.pixels.count() as f64) - (index / self.width * self.width) as f64;
+        let row = (index / self.width) as f64 + random_double();
+        let size = self.width.max(self.height) as f64;
+        (
+            (2.0 * column - self.width as f64) / size,
+            (self.height as f64 - 2.0 * row) / size,
+        )
+    }
+}
+
+#[derive(Clone)]
+pub enum ApertureShape {
+    Circle,
+    Polygon { blades: usize, rotation: f64 }, //straight blades, rotation in degrees
+    Image(Arc<ApertureImage>),
+}
+
+impl ApertureShape {
+    //uniform over the opening, inside the unit disk
+    fn sample(&self) -> (f64, f64) {
+        match self {
+            ApertureShape::Circle => {
+                let p = Vec3::random_in_unit_disk();
+                (p.x, p.y)
+            }
+            ApertureShape::Polygon { blades, rotation } => {
+                //pick one of the equal triangles around the center, then a point in it
+                let blades = (*blades).max(3);
+                let i = ((random_double() * blades as f64) as usize).min(blades - 1);
+                let step = 2.0 * PI / blades as f64;
+                let a0 = degrees_to_radians(*rotation) + step * i as f64;
+                let a1 = a0 + step;
+                let mut s = random_double();
+                let mut t = random_double();
+                if s + t > 1.0 {
+                    s = 1.0 - s;
+                    t = 1.0 - t;
+                }
+                (s * a0.cos() + t * a1.cos(), s * a0.sin() + t * a1.sin())
+            }
+            ApertureShape::Image(image) => image.sample(),
+        }
+    }
+}
+
+//the shape of out of focus highlights
+#[derive(Clone)]
+pub struct Bokeh {
+    pub shape: ApertureShape,
+    pub squeeze: f64, //height over width of the bokeh, anamorphic lenses are 1.33 to 2
+    pub cat_eye: f64, //0 is off, toward 1 the lens barrel clips the opening at the image edges
+}
+
+impl Default for Bokeh {
+    fn default() -> Self {
+        Self::new(ApertureShape::Circle)
+    }
+}
+
+impl Bokeh {
+    pub fn new(shape: ApertureShape) -> Self {
+        Self {
+            shape: (shape),
+            squeeze: 1.0,
+            cat_eye: 0.0,
+        }
+    }
+
+    pub fn polygon(blades: usize, rotation: f64) -> Self {
+        Self::new(ApertureShape::Polygon {
+            blades: (blades),
+            rotation: (rotation),
+        })
+    }
+
+    pub fn image(filename: &str) -> Self {
+        Self::new(ApertureShape::Image(Arc::new(ApertureImage::new(filename))))
+    }
+
+    //lens position for the pixel at (s, t), in units of the lens radius, none when the lens
+    //barrel blocks it; blocked samples carry no light, so clipped pixels get darker
+    pub fn sample(&self, s: f64, t: f64) -> Option<(f64, f64)> {
+        let (mut x, y) = self.shape.sample();
+        if self.cat_eye > 0.0 {
+            //a second circle of the same size, shifted along the pixel's offset from the
+            //image center, the light only gets through where both overlap
+            let cx = (s - 0.5) * 2.0 * self.cat_eye.min(1.0);
+            let cy = (t - 0.5) * 2.0 * self.cat_eye.min(1.0);
+            if (x - cx) * (x - cx) + (y - cy) * (y - cy) > 1.0 {
+                return None;
+            }
+        }
+        if self.squeeze > 0.0 {
+            x /= self.squeeze;
+        }
+        Some((x, y))
+    }
+}
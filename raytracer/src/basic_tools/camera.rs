@@ -1,11 +1,12 @@
-use crate::hittable::hittable_origin::random_t;
+use crate::hittable::hittable_origin::{random_t, HitRecord, Hittable};
 
 use super::{
+    aperture::Bokeh,
     ray::Ray,
-    vec3::{Color, Point, Vec3},
+    vec3::{Point, Vec3},
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Camera {
     pub origin: Point,
     pub horizontal: Vec3,
@@ -17,6 +18,38 @@ pub struct Camera {
     pub lens_radius: f64,
     pub time0: f64,
     pub time1: f64,
+    pub motion: Option<CameraMotion>,
+    pub exposure: f64, //scales the radiance reaching the image
+    pub projection: Projection,
+    pub bokeh: Bokeh,
+}
+
+//how fisheye lenses spread the angle from the axis over the image circle
+#[derive(Clone, Copy, PartialEq)]
+pub enum FisheyeMapping {
+    Equidistant, //radius grows with the angle
+    Equisolid,   //equal areas cover equal solid angles
+}
+
+//how image coordinates turn into rays, all keep the view basis of Camera::new
+#[derive(Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,                  //thin lens, uses vfov, aperture and focus_dist
+    Orthographic { height: f64 }, //parallel rays, height of the view in scene units
+    Equirectangular,              //full 360 by 180 degree panorama
+    Fisheye { fov: f64, mapping: FisheyeMapping }, //circular image, fov across the circle in degrees
+    OmniStereo { eye_offset: f64 }, //panorama for one eye, -interocular/2 left and +interocular/2 right
+}
+
+//where a moving camera is when the shutter closes at time1
+#[derive(Clone, Copy)]
+pub struct CameraMotion {
+    pub origin: Point,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    pub lower_left_corner: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
 }
 
 pub const PI: f64 = std::f64::consts::PI;
@@ -61,6 +94,114 @@ impl Camera {
             lens_radius: aperture / 2.0,
             time0: t0,
             time1: t1,
+            motion: None,
+            exposure: 1.0,
+            projection: Projection::Perspective,
+            bokeh: Bokeh::default(),
+        }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Camera {
+        Camera {
+            projection: (projection),
+            ..self
+        }
+    }
+
+    pub fn with_bokeh(self, bokeh: Bokeh) -> Camera {
+        Camera {
+            bokeh: (bokeh),
+            ..self
+        }
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.horizontal.length() / self.vertical.length()
+    }
+
+    //left and right eye, both looking straight ahead through one shared window at the
+    //convergence distance, so objects there sit on the screen; the focus moves there too
+    pub fn stereo(&self, interocular: f64, convergence: f64) -> [Camera; 2] {
+        let center = self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0;
+        let k = convergence / (center - self.origin).length();
+        let eye = |side: f64| {
+            let mut camera = Camera {
+                origin: self.origin + self.u * (side * interocular / 2.0),
+                horizontal: self.horizontal * k,
+                vertical: self.vertical * k,
+                lower_left_corner: self.origin + (self.lower_left_corner - self.origin) * k,
+                ..self.clone()
+            };
+            if let Some(end) = &self.motion {
+                camera.motion = Some(CameraMotion {
+                    origin: end.origin + end.u * (side * interocular / 2.0),
+                    horizontal: end.horizontal * k,
+                    vertical: end.vertical * k,
+                    lower_left_corner: end.origin + (end.lower_left_corner - end.origin) * k,
+                    ..*end
+                });
+            }
+            camera
+        };
+        [eye(-1.0), eye(1.0)]
+    }
+
+    //omni-directional stereo panoramas for the left and right eye
+    pub fn omni_stereo(&self, interocular: f64) -> [Camera; 2] {
+        [
+            self.clone().with_projection(Projection::OmniStereo {
+                eye_offset: -interocular / 2.0,
+            }),
+            self.clone().with_projection(Projection::OmniStereo {
+                eye_offset: interocular / 2.0,
+            }),
+        ]
+    }
+
+    //lookfrom and lookat at time0 and time1, in between the view is blended linearly
+    pub fn new_moving(
+        lookfrom: [Point; 2],
+        lookat: [Point; 2],
+        vup: Vec3,
+        vfov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+        t0: f64,
+        t1: f64,
+    ) -> Camera {
+        let start = Camera::new(
+            lookfrom[0],
+            lookat[0],
+            vup,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_dist,
+            t0,
+            t1,
+        );
+        let end = Camera::new(
+            lookfrom[1],
+            lookat[1],
+            vup,
+            vfov,
+            aspect_ratio,
+            aperture,
+            focus_dist,
+            t0,
+            t1,
+        );
+        Camera {
+            motion: Some(CameraMotion {
+                origin: end.origin,
+                horizontal: end.horizontal,
+                vertical: end.vertical,
+                lower_left_corner: end.lower_left_corner,
+                u: end.u,
+                v: end.v,
+            }),
+            ..start
         }
     }
 
@@ -140,6 +281,40 @@ impl Camera {
             1.0,
         )
     }
+    //everything around the whale scene, seen from between the whale and the camera
+    pub fn whale_panorama() -> Self {
+        Camera::new(
+            Point::new(278.0, 350.0, 0.0),
+            Point::new(278.0, 350.0, 400.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            2.0,
+            0.0,
+            10.0,
+            0.0,
+            1.0,
+        )
+        .with_projection(Projection::Equirectangular)
+    }
+    pub fn outdoor_whale() -> Self {
+        let aspect_ratio = 16.0 / 9.0;
+        let lookfrom = Point::new(278.0, 350.0, -800.0);
+        let lookat = Point::new(278.0, 250.0, 0.0);
+        let vup = Vec3::new(0.0, 1.0, 0.0);
+        let dist_to_focus = 10.0;
+        let aperture = 0.0;
+        Camera::new(
+            lookfrom,
+            lookat,
+            vup,
+            40.0,
+            aspect_ratio,
+            aperture,
+            dist_to_focus,
+            0.0,
+            1.0,
+        )
+    }
     pub fn new_random_scence() -> Self {
         let aspect_ratio = 16.0 / 9.0;
         let lookfrom = Point::new(13.0, 2.0, 3.0);
@@ -200,17 +375,193 @@ impl Camera {
         )
     }
     pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = Vec3::random_in_unit_disk() * self.lens_radius;
-        let offset = self.u * rd.x + self.v * rd.y;
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + self.horizontal * s + self.vertical * t - self.origin - offset,
-            random_t(self.time0, self.time1),
-        )
+        let time = random_t(self.time0, self.time1);
+        let mut origin = self.origin;
+        let mut horizontal = self.horizontal;
+        let mut vertical = self.vertical;
+        let mut lower_left_corner = self.lower_left_corner;
+        let mut u = self.u;
+        let mut v = self.v;
+        if let Some(end) = &self.motion {
+            let f = if self.time1 > self.time0 {
+                (time - self.time0) / (self.time1 - self.time0)
+            } else {
+                0.0
+            };
+            origin = origin + (end.origin - origin) * f;
+            horizontal = horizontal + (end.horizontal - horizontal) * f;
+            vertical = vertical + (end.vertical - vertical) * f;
+            lower_left_corner = lower_left_corner + (end.lower_left_corner - lower_left_corner) * f;
+            u = u + (end.u - u) * f;
+            v = v + (end.v - v) * f;
+        }
+        let w = Vec3::cross(u, v);
+        let aspect_ratio = horizontal.length() / vertical.length();
+        match self.projection {
+            Projection::Perspective => {
+                let offset = if self.lens_radius > 0.0 {
+                    match self.bokeh.sample(s, t) {
+                        Some((x, y)) => (u * x + v * y) * self.lens_radius,
+                        None => return Ray::new(origin, Vec3::new(0.0, 0.0, 0.0), time),
+                    }
+                } else {
+                    Vec3::new(0.0, 0.0, 0.0)
+                };
+                Ray::new(
+                    origin + offset,
+                    lower_left_corner + horizontal * s + vertical * t - origin - offset,
+                    time,
+                )
+            }
+            Projection::Orthographic { height } => {
+                let offset = u * ((s - 0.5) * height * aspect_ratio) + v * ((t - 0.5) * height);
+                Ray::new(origin + offset, -w, time)
+            }
+            Projection::Equirectangular => {
+                let phi = 2.0 * PI * (s - 0.5);
+                let theta = PI * (t - 0.5);
+                let direction = (u * phi.sin() - w * phi.cos()) * theta.cos() + v * theta.sin();
+                Ray::new(origin, direction, time)
+            }
+            Projection::OmniStereo { eye_offset } => {
+                //every column is seen from its own point on the circle the eyes turn on
+                let phi = 2.0 * PI * (s - 0.5);
+                let theta = PI * (t - 0.5);
+                let direction = (u * phi.sin() - w * phi.cos()) * theta.cos() + v * theta.sin();
+                let side = u * phi.cos() + w * phi.sin();
+                Ray::new(origin + side * eye_offset, direction, time)
+            }
+            Projection::Fisheye { fov, mapping } => {
+                //the image circle touches the top and bottom of the image
+                let x = (s - 0.5) * aspect_ratio * 2.0;
+                let y = (t - 0.5) * 2.0;
+                let r = (x * x + y * y).sqrt();
+                if r > 1.0 {
+                    return Ray::new(origin, Vec3::new(0.0, 0.0, 0.0), time);
+                }
+                let theta_max = degrees_to_radians(fov) / 2.0;
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * theta_max,
+                    FisheyeMapping::Equisolid => 2.0 * (r * (theta_max / 2.0).sin()).asin(),
+                };
+                let (cos_phi, sin_phi) = if r > 0.0 { (x / r, y / r) } else { (1.0, 0.0) };
+                let direction = (u * cos_phi + v * sin_phi) * theta.sin() - w * theta.cos();
+                Ray::new(origin, direction, time)
+            }
+        }
     }
 }
 
-pub fn get_background(_t: f64) -> Color {
-    //Color::new(1.0, 192.0 / 255.0, 203.0 / 255.0) * (1.0 - t) + Color::new(0.5, 0.7, 1.0) * t
-    Color::new(0.0, 0.0, 0.0)
+//f/2.8 for 1/60 s at ISO 100 gives exposure 1, the brightness the scenes were lit for
+const REFERENCE_EV100: f64 = 8.877;
+//a pinhole has no f-number to meter with, it is exposed as if it were set to this one
+const REFERENCE_F_NUMBER: f64 = 2.8;
+
+//a camera described like a real one, lengths on the camera side in mm
+#[derive(Clone)]
+pub struct PhysicalCamera {
+    pub lookfrom: Point,
+    pub lookat: Point,
+    pub vup: Vec3,
+    pub aspect_ratio: f64,
+    pub focal_length: f64,
+    pub sensor_width: f64,
+    pub f_number: f64,       //infinite for a pinhole
+    pub focus_distance: f64, //scene units
+    pub shutter_open: f64,   //seconds, also the ray time range
+    pub shutter_close: f64,
+    pub iso: f64,
+    pub mm_per_unit: f64, //size of one scene unit, turns the aperture into scene units
+    pub bokeh: Bokeh,
+}
+
+impl PhysicalCamera {
+    //a 50mm lens on a full frame sensor, focused on lookat
+    pub fn new(lookfrom: Point, lookat: Point, aspect_ratio: f64) -> Self {
+        Self {
+            lookfrom: (lookfrom),
+            lookat: (lookat),
+            vup: (Vec3::new(0.0, 1.0, 0.0)),
+            aspect_ratio: (aspect_ratio),
+            focal_length: 50.0,
+            sensor_width: 36.0,
+            f_number: 2.8,
+            focus_distance: ((lookat - lookfrom).length()),
+            shutter_open: 0.0,
+            shutter_close: 1.0 / 60.0,
+            iso: 100.0,
+            mm_per_unit: 10.0,
+            bokeh: Bokeh::default(),
+        }
+    }
+
+    pub fn vfov(&self) -> f64 {
+        let sensor_height = self.sensor_width / self.aspect_ratio;
+        2.0 * (sensor_height / (2.0 * self.focal_length)).atan() * 180.0 / PI
+    }
+
+    pub fn lens_radius(&self) -> f64 {
+        if !self.f_number.is_finite() {
+            return 0.0;
+        }
+        self.focal_length / self.f_number / 2.0 / self.mm_per_unit
+    }
+
+    pub fn ev100(&self) -> f64 {
+        let f_number = if self.f_number.is_finite() {
+            self.f_number
+        } else {
+            REFERENCE_F_NUMBER
+        };
+        let shutter = self.shutter_close - self.shutter_open;
+        (f_number * f_number / shutter).log2() - (self.iso / 100.0).log2()
+    }
+
+    //relative to REFERENCE_EV100, one stop brighter doubles it; shutter and iso count for a pinhole too
+    pub fn exposure(&self) -> f64 {
+        (REFERENCE_EV100 - self.ev100()).exp2()
+    }
+
+    //focus on whatever is under the center pixel, keeps the focus distance when nothing is
+    pub fn autofocus(&mut self, world: &dyn Hittable) {
+        let direction = Vec3::unit_vector(self.lookat - self.lookfrom);
+        let r = Ray::new(self.lookfrom, direction, self.shutter_open);
+        let mut rec = HitRecord::default();
+        if world.hit(&r, 0.001, f64::MAX, &mut rec) {
+            self.focus_distance = rec.t;
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let camera = Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov(),
+            self.aspect_ratio,
+            self.lens_radius() * 2.0,
+            self.focus_distance,
+            self.shutter_open,
+            self.shutter_close,
+        );
+        Camera {
+            exposure: self.exposure(),
+            bokeh: self.bokeh.clone(),
+            ..camera
+        }
+    }
+
+    //the view of Camera::whale through a 28mm lens wide open, focus set by autofocus;
+    //seven blades turn the out of focus planets into heptagons
+    pub fn whale() -> Self {
+        let mut camera = PhysicalCamera::new(
+            Point::new(278.0, 278.0, -800.0),
+            Point::new(278.0, 278.0, 0.0),
+            16.0 / 9.0,
+        );
+        camera.focal_length = 28.0;
+        camera.f_number = 2.8;
+        camera.bokeh = Bokeh::polygon(7, 90.0);
+        camera
+    }
 }
@@ -0,0 +1,343 @@
+use super::camera::degrees_to_radians;
+use super::vec3::{Point, Vec3};
+use std::ops::Mul;
+
+//row major affine transform acting on column vectors, a * b applies b first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Self {
+        Self { m: (m) }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(factor: Vec3) -> Self {
+        Self::new([
+            [factor.x, 0.0, 0.0, 0.0],
+            [0.0, factor.y, 0.0, 0.0],
+            [0.0, 0.0, factor.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    //right handed in degrees, counterclockwise seen from the tip of the axis, like RotateY
+    pub fn rotate(axis: Vec3, angle: f64) -> Self {
+        let a = Vec3::unit_vector(axis);
+        let radians = degrees_to_radians(angle);
+        let (sin_theta, cos_theta) = radians.sin_cos();
+        let c = 1.0 - cos_theta;
+        Self::new([
+            [
+                a.x * a.x * c + cos_theta,
+                a.x * a.y * c - a.z * sin_theta,
+                a.x * a.z * c + a.y * sin_theta,
+                0.0,
+            ],
+            [
+                a.y * a.x * c + a.z * sin_theta,
+                a.y * a.y * c + cos_theta,
+                a.y * a.z * c - a.x * sin_theta,
+                0.0,
+            ],
+            [
+                a.z * a.x * c - a.y * sin_theta,
+                a.z * a.y * c + a.x * sin_theta,
+                a.z * a.z * c + cos_theta,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    //RotateX turns the other way from rotate about x, this matches RotateX
+    pub fn rotate_x(angle: f64) -> Self {
+        Self::rotate(Vec3::new(1.0, 0.0, 0.0), -angle)
+    }
+
+    pub fn rotate_y(angle: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotate_z(angle: f64) -> Self {
+        Self::rotate(Vec3::new(0.0, 0.0, 1.0), angle)
+    }
+
+    //places an object at from with its +z axis pointing at `at` and +y as close to vup as possible
+    pub fn look_at(from: Point, at: Point, vup: Vec3) -> Self {
+        let w = Vec3::unit_vector(at - from);
+        let u = Vec3::unit_vector(Vec3::cross(vup, w));
+        let v = Vec3::cross(w, u);
+        Self::new([
+            [u.x, v.x, w.x, from.x],
+            [u.y, v.y, w.y, from.y],
+            [u.z, v.z, w.z, from.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                t[j][i] = *value;
+            }
+        }
+        Self::new(t)
+    }
+
+    //determinant of the upper left 3x3 block, the volume scale of the transform
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    //gauss-jordan with partial pivoting, none when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+        for col in 0..4 {
+            let mut pivot = col;
+            for row in col + 1..4 {
+                if a[row][col].abs() > a[pivot][col].abs() {
+                    pivot = row;
+                }
+            }
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let p = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= p;
+                inv[col][j] /= p;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let f = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= f * a[col][j];
+                    inv[row][j] -= f * inv[col][j];
+                }
+            }
+        }
+        Some(Self::new(inv))
+    }
+
+    pub fn transform_point(&self, p: &Point) -> Point {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 {
+            Point::new(x, y, z)
+        } else {
+            Point::new(x, y, z) / w
+        }
+    }
+
+    //directions ignore the translation
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Self::new(m)
+    }
+}
+
+//unit quaternion for rotations that have to be interpolated
+#[derive(Clone, Copy, Debug)]
+pub struct Quaternion {
+    pub w: f64,
+    pub v: Vec3,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::new(1.0, Vec3::new(0.0, 0.0, 0.0))
+    }
+}
+
+impl Quaternion {
+    pub fn new(w: f64, v: Vec3) -> Self {
+        Self { w: (w), v: (v) }
+    }
+
+    //same convention as Matrix4::rotate, right handed
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Self {
+        let half = degrees_to_radians(angle) / 2.0;
+        Self::new(half.cos(), Vec3::unit_vector(axis) * half.sin())
+    }
+
+    //rotates about x, then y, then z, like the product rotate_z * rotate_y * rotate_x,
+    //so the x angle turns the same way as RotateX
+    pub fn from_euler(angles: Vec3) -> Self {
+        Self::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), angles.z)
+            * Self::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), angles.y)
+            * Self::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), -angles.x)
+    }
+
+    pub fn dot(a: &Self, b: &Self) -> f64 {
+        a.w * b.w + Vec3::dot(&a.v, &b.v)
+    }
+
+    pub fn normalized(&self) -> Self {
+        let len = Self::dot(self, self).sqrt();
+        Self::new(self.w / len, self.v / len)
+    }
+
+    //constant angular speed along the shorter arc
+    pub fn slerp(a: Self, b: Self, t: f64) -> Self {
+        let mut b = b;
+        let mut cos_theta = Self::dot(&a, &b);
+        if cos_theta < 0.0 {
+            b = Self::new(-b.w, -b.v);
+            cos_theta = -cos_theta;
+        }
+        if cos_theta > 0.9995 {
+            return Self::new(a.w + (b.w - a.w) * t, a.v + (b.v - a.v) * t).normalized();
+        }
+        let theta = cos_theta.acos();
+        let wa = ((1.0 - t) * theta).sin() / theta.sin();
+        let wb = (t * theta).sin() / theta.sin();
+        Self::new(a.w * wa + b.w * wb, a.v * wa + b.v * wb)
+    }
+
+    //rotation angle in radians, between 0 and 2pi
+    pub fn angle(&self) -> f64 {
+        2.0 * self.w.abs().min(1.0).acos()
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.v)
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        let (w, x, y, z) = (self.w, self.v.x, self.v.y, self.v.z);
+        Matrix4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.w * other.w - Vec3::dot(&self.v, &other.v),
+            other.v * self.w + self.v * other.w + Vec3::cross(self.v, other.v),
+        )
+    }
+}
+
+//translate * rotate * scale, kept apart so two poses can be blended
+#[derive(Clone, Copy, Debug)]
+pub struct Pose {
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Quaternion::default(),
+            Vec3::new(1.0, 1.0, 1.0),
+        )
+    }
+}
+
+impl Pose {
+    pub fn new(translation: Vec3, rotation: Quaternion, scale: Vec3) -> Self {
+        Self {
+            translation: (translation),
+            rotation: (rotation),
+            scale: (scale),
+        }
+    }
+
+    pub fn lerp(a: &Self, b: &Self, t: f64) -> Self {
+        Self::new(
+            a.translation + (b.translation - a.translation) * t,
+            Quaternion::slerp(a.rotation, b.rotation, t),
+            a.scale + (b.scale - a.scale) * t,
+        )
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        Matrix4::translate(self.translation) * self.rotation.matrix() * Matrix4::scale(self.scale)
+    }
+
+    //built directly, cheaper than Matrix4::inverse for every ray
+    pub fn inverse(&self) -> Matrix4 {
+        let s = self.scale;
+        Matrix4::scale(Vec3::new(1.0 / s.x, 1.0 / s.y, 1.0 / s.z))
+            * self.rotation.conjugate().matrix()
+            * Matrix4::translate(-self.translation)
+    }
+}
@@ -1,5 +1,9 @@
+pub mod animation;
+pub mod aperture;
 pub mod camera;
+pub mod matrix;
 pub mod preview;
 pub mod ray;
 pub mod render;
+pub mod spectrum;
 pub mod vec3;
@@ -17,15 +17,14 @@ use crate::hittable::{
     hittable_origin::{HitRecord, Hittable},
 };
 use rand::{prelude::SliceRandom, thread_rng};
-fn scale_line(r: &Ray, world: &dyn Hittable, camera: Camera) -> f64 {
+//measured from where the ray starts, which is not the camera origin for orthographic views
+fn scale_line(r: &Ray, world: &dyn Hittable) -> f64 {
     let mut rec = HitRecord::default();
-    if !world.hit(r, 0.001, INFINITY, &mut rec) {
+    if r.direct.near_zero() || !world.hit(r, 0.001, INFINITY, &mut rec) {
         return INFINITY;
     }
-    ((camera.origin.x - rec.p.x).powi(2)
-        + (camera.origin.y - rec.p.y).powi(2)
-        + (camera.origin.z - rec.p.z).powi(2))
-    .sqrt()
+    ((r.point.x - rec.p.x).powi(2) + (r.point.y - rec.p.y).powi(2) + (r.point.z - rec.p.z).powi(2))
+        .sqrt()
 }
 
 pub fn preview() {
@@ -79,7 +78,7 @@ pub fn preview() {
         }
 
         let world_thread = bvhworld.clone();
-        let camera_thread = camera;
+        let camera_thread = camera.clone();
 
         let t_random_pixel = random_pixal.clone();
 
@@ -109,7 +108,7 @@ pub fn preview() {
                         let u = (x_map as f64) / (WIDTH as f64);
                         let v = (y_map as f64) / (HEIGHT as f64);
                         let r = camera_thread.get_ray(u, v);
-                        let depth = scale_line(&r, &world_thread, camera_thread);
+                        let depth = scale_line(&r, &world_thread);
                         depth_thread.push(depth);
 
                         progress += 1;
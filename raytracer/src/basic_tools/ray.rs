@@ -4,6 +4,7 @@ pub struct Ray {
     pub point: Vec3,
     pub direct: Vec3,
     pub time: f64,
+    pub wavelength: f64, //nm, 0 while the ray still carries all of rgb
 }
 
 impl Ray {
@@ -12,6 +13,16 @@ impl Ray {
             point: p,
             direct: d,
             time: t,
+            wavelength: 0.0,
+        }
+    }
+
+    pub fn new_wavelength(p: Vec3, d: Vec3, t: f64, lambda: f64) -> Self {
+        Self {
+            point: p,
+            direct: d,
+            time: t,
+            wavelength: lambda,
         }
     }
 
@@ -11,26 +11,131 @@ use image::{ImageBuffer, RgbImage};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
 use crate::basic_tools::{
-    camera::{get_background, Camera},
+    animation::Animation,
+    camera::{Camera, PhysicalCamera},
     ray::Ray,
+    spectrum::{sample_wavelength, wavelength_weight},
     vec3::{Color, Vec3},
 };
 use crate::hittable::{
     bvh::BVHNode,
     hittable_list::HittableList,
     hittable_origin::{clamp, random_double, HitRecord, Hittable},
-    pdf::{HittablePDF, MixturePDF, PDF},
+    pdf::{BackgroundPDF, HittablePDF, MixturePDF, PDF},
 };
-use crate::material::metal::ScatterRecord;
+use crate::light::{
+    background::{Background, SolidBackground},
+    environment::EnvironmentMap,
+    light_list::LightList,
+    light_origin::LightSample,
+    sky::PreethamSky,
+    spot_light::SpotLight,
+};
+use crate::material::{metal::ScatterRecord, subsurface::SubsurfaceMedium};
 use rand::{prelude::SliceRandom, thread_rng};
 
-fn ray_color(r: &Ray, t: f64, world: &dyn Hittable, light: Arc<dyn Hittable>, depth: i32) -> Color {
-    if depth <= 0 {
+//longest random walk through a subsurface medium before the path is dropped
+const MAX_MEDIUM_EVENTS: usize = 1024;
+
+fn delta_light_color(
+    r: &Ray,
+    rec: &HitRecord,
+    srec: &ScatterRecord,
+    world: &dyn Hittable,
+    delta_lights: &LightList,
+) -> Color {
+    let mut col = Color::new(0.0, 0.0, 0.0);
+    for light in &delta_lights.lights {
+        let mut lrec = LightSample::default();
+        if !light.sample(&rec.p, &mut lrec) {
+            continue;
+        }
+        let shadow_ray = Ray::new(rec.p, lrec.direction, r.time);
+        let mut shadow_rec = HitRecord::default();
+        if world.hit(&shadow_ray, 0.001, lrec.distance - 0.001, &mut shadow_rec) {
+            continue;
+        }
+        col += rec
+            .mat_ptr
+            .as_ref()
+            .unwrap()
+            .scattering_value(r, rec, srec, &shadow_ray)
+            * lrec.radiance;
+    }
+    col
+}
+
+//medium is what the ray travels through, none for air
+fn ray_color(
+    r: &Ray,
+    background: Arc<dyn Background>,
+    world: &dyn Hittable,
+    light: Arc<HittableList>,
+    delta_lights: &LightList,
+    medium: Option<SubsurfaceMedium>,
+    depth: i32,
+) -> Color {
+    //cameras send no direction for samples that carry no light, like the corners of a fisheye
+    //or lens samples blocked by the barrel
+    if depth <= 0 || r.direct.near_zero() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    if let Some(medium) = medium {
+        //free flight inside a subsurface medium, scattering until the walk reaches the boundary;
+        //scatter events have their own budget and end by russian roulette, not by depth
+        let mut walk = Ray::new_wavelength(r.point, r.direct, r.time, r.wavelength);
+        let mut weight = Color::new(1.0, 1.0, 1.0);
+        for _i in 0..MAX_MEDIUM_EVENTS {
+            let mut rec = HitRecord::default();
+            let length = walk.direct.length();
+            let t_max = if world.hit(&walk, 0.001, f64::INFINITY, &mut rec) {
+                rec.t * length
+            } else {
+                f64::INFINITY
+            };
+            let mut w = Color::new(1.0, 1.0, 1.0);
+            let event = medium.sample_distance(t_max, &mut w);
+            weight = weight * w;
+            let distance = match event {
+                Some(distance) => distance,
+                None => {
+                    return ray_color(&walk, background, world, light, delta_lights, None, depth)
+                        * weight;
+                }
+            };
+            let survive = weight.x.max(weight.y).max(weight.z).min(1.0);
+            if random_double() >= survive {
+                return Color::new(0.0, 0.0, 0.0);
+            }
+            weight = weight / survive;
+            let p = walk.at(distance / length);
+            walk = Ray::new_wavelength(
+                p,
+                medium.sample_phase(&walk.direct),
+                walk.time,
+                walk.wavelength,
+            );
+        }
         return Color::new(0.0, 0.0, 0.0);
     }
     let mut rec = HitRecord::default();
-    if !world.hit(r, 0.001, INFINITY, &mut rec) {
-        return get_background(t);
+    let hit = world.hit(r, 0.001, INFINITY, &mut rec);
+    if !hit {
+        return background.value(r);
+    }
+    //dispersive surfaces need one wavelength, the rest of the path is traced with it
+    if r.wavelength <= 0.0 && rec.mat_ptr.as_ref().unwrap().is_dispersive() {
+        let lambda = sample_wavelength(random_double());
+        let spectral_ray = Ray::new_wavelength(r.point, r.direct, r.time, lambda);
+        return ray_color(
+            &spectral_ray,
+            background,
+            world,
+            light,
+            delta_lights,
+            None,
+            depth,
+        ) * wavelength_weight(lambda);
     }
     let mut srec = ScatterRecord::default();
     let emitted = rec
@@ -44,24 +149,58 @@ fn ray_color(r: &Ray, t: f64, world: &dyn Hittable, light: Arc<dyn Hittable>, de
     }
 
     if srec.is_specular {
-        let a = ray_color(&srec.specular_ray, t, world, light, depth - 1) * srec.attenuation;
-        return a;
+        srec.specular_ray.wavelength = r.wavelength;
+        return emitted
+            + ray_color(
+                &srec.specular_ray,
+                background,
+                world,
+                light,
+                delta_lights,
+                srec.medium,
+                depth - 1,
+            ) * srec.attenuation;
+    }
+    let mut light_pdfs: Vec<Arc<dyn PDF>> = Vec::new();
+    if !light.objects.is_empty() {
+        light_pdfs.push(Arc::new(HittablePDF::new(light.clone(), rec.p)));
     }
-    let light_ptr = Arc::new(HittablePDF::new(light.clone(), rec.p));
-    let p = MixturePDF::new(light_ptr, srec.pdf_ptr.as_ref().unwrap().clone());
+    if background.importance_sampled() {
+        light_pdfs.push(Arc::new(BackgroundPDF::new(background.clone())));
+    }
+    let surface_pdf = srec.pdf_ptr.as_ref().unwrap().clone();
+    let p: Arc<dyn PDF> = match light_pdfs.len() {
+        0 => surface_pdf,
+        1 => Arc::new(MixturePDF::new(light_pdfs[0].clone(), surface_pdf)),
+        _ => Arc::new(MixturePDF::new(
+            Arc::new(MixturePDF::new(
+                light_pdfs[0].clone(),
+                light_pdfs[1].clone(),
+            )),
+            surface_pdf,
+        )),
+    };
 
-    let scattered = Ray::new(rec.p, p.generate(), r.time);
+    let scattered = Ray::new_wavelength(rec.p, p.generate(), r.time, r.wavelength);
     let pdf = p.value(&scattered.direct);
     //  println!("pdf:{}", pdf);
+    let direct = delta_light_color(r, &rec, &srec, world, delta_lights);
     emitted
-        + ray_color(&scattered, t, world, light, depth - 1)
-            * srec.attenuation
-            * rec
-                .mat_ptr
-                .clone()
-                .as_ref()
-                .unwrap()
-                .scattering_pdf(r, &rec, &scattered)
+        + direct
+        + ray_color(
+            &scattered,
+            background,
+            world,
+            light,
+            delta_lights,
+            None,
+            depth - 1,
+        ) * rec
+            .mat_ptr
+            .clone()
+            .as_ref()
+            .unwrap()
+            .scattering_value(r, &rec, &srec, &scattered)
             / pdf
 }
 
@@ -69,29 +208,317 @@ pub fn render() {
     print!("{}[2J", 27 as char); // Clear screen 27 as char --> esc
     print!("{esc}[2J{esc}[1;1H", esc = 27 as char); // Set cursor position as 1,1
 
-    const ASPECT_RATIO: f64 = 16.0 / 9.0;
-    const HEIGHT: usize = 900;
-    const WIDTH: usize = (ASPECT_RATIO * HEIGHT as f64) as usize;
-    let quality = 100; // From 0 to 100
     let path = "output/try7.jpg";
-    let samples_per_pixel = 1000;
-    let max_depth = 50;
+    //Some(Animation::whale()) renders output/frame_0000.png, frame_0001.png, ... instead
+    let animation: Option<Animation> = None;
+    //several cameras from the one still scene, e.g. a stereo pair converging on the whale
+    //Some((|c: &Camera| c.stereo(6.4, 1200.0).to_vec(), ViewLayout::SideBySide)), or
+    //Some((|_: &Camera| Camera::whale_panorama().omni_stereo(6.4).to_vec(), ViewLayout::OverUnder))
+    let views: Option<(ViewSet, ViewLayout)> = None;
+    //only part of the still, e.g. the caustics under the glass cloud at 10000 spp pasted over
+    //an earlier render: Some(RenderRegion::new(CropWindow::new(0.3, 0.55, 0.6, 0.85), 10000,
+    //RegionOutput::Composite(String::from("output/try7.jpg"))))
+    let region: Option<RenderRegion> = None;
+    //Some(PhysicalCamera::whale()) shoots the still through a real lens with depth of field,
+    //autofocused on the whale, instead of the pinhole Camera::whale()
+    let lens: Option<PhysicalCamera> = None;
+    //point, spot and directional lights are sampled by shadow rays, e.g. a spot from above on the
+    //whale: Some(SpotLight::new(Vec3::new(300.0, 1200.0, 100.0), Vec3::new(300.0, 350.0, 400.0),
+    //Color::new(1.0, 0.95, 0.85), 1.0e6, 25.0, 15.0))
+    let spot: Option<SpotLight> = None;
+    //an equirectangular map under img/ lights the scene instead of the black background and is
+    //importance sampled with the lamp, e.g. Some("studio.hdr")
+    let environment: Option<&str> = None;
+    //Some(PreethamSky::new(35.0, 120.0, 3.0, 0.1)) renders the outdoor whale on open ground under
+    //a daylight sky, lit by the sky's matching sun instead of the lamp
+    let sky: Option<PreethamSky> = None;
+
+    let lamp = Arc::new(match sky {
+        None => HittableList::whale_lights(),
+        Some(_) => HittableList::default(),
+    });
+    let mut delta_lights = LightList::new();
+    if let Some(spot) = spot {
+        delta_lights.add(Arc::new(spot));
+    }
+    if let Some(sky) = sky {
+        delta_lights.add(Arc::new(sky.sun(3.0)));
+    }
+    let delta_lamp = Arc::new(delta_lights);
+    let background: Arc<dyn Background> = match (sky, environment) {
+        (Some(sky), _) => Arc::new(sky),
+        (None, Some(filename)) => Arc::new(EnvironmentMap::new(filename, 1.0, 0.0)),
+        (None, None) => Arc::new(SolidBackground::new(Color::new(0.0, 0.0, 0.0))),
+    };
+
+    match animation {
+        None => {
+            let world = match sky {
+                None => HittableList::whale(),
+                Some(_) => HittableList::outdoor_whale(),
+            };
+            let bvhworld = BVHNode::new(world.objects.clone(), 0, world.objects.len(), 0.0, 1.0);
+            let camera = match lens {
+                None if sky.is_some() => Camera::outdoor_whale(),
+                None => Camera::whale(),
+                Some(mut lens) => {
+                    lens.autofocus(&bvhworld);
+                    lens.camera()
+                }
+            };
+            match views {
+                None => render_frame(
+                    &camera,
+                    &bvhworld,
+                    &lamp,
+                    &delta_lamp,
+                    &background,
+                    region.as_ref(),
+                    path,
+                ),
+                Some((cameras, layout)) => {
+                    let cameras = cameras(&camera);
+                    render_views(
+                        &cameras,
+                        &bvhworld,
+                        &lamp,
+                        &delta_lamp,
+                        &background,
+                        layout,
+                        path,
+                    );
+                }
+            }
+        }
+        Some(mut animation) => {
+            //the static part and every mesh's BVH are built once, each frame only poses instances
+            let stage = HittableList::whale_stage();
+            let timeline = animation.timeline;
+            for frame in timeline.start..=timeline.end {
+                println!("Frame {} of {}", frame, timeline.end);
+                let (camera, instances) = animation.frame(frame);
+                let mut world = stage.clone();
+                world.add(Arc::new(instances));
+                let bvhworld =
+                    BVHNode::new(world.objects.clone(), 0, world.objects.len(), 0.0, 1.0);
+                render_frame(
+                    &camera,
+                    &bvhworld,
+                    &lamp,
+                    &delta_lamp,
+                    &background,
+                    None,
+                    &timeline.frame_path("output", frame),
+                );
+            }
+        }
+    }
+    exit(0);
+}
+
+const HEIGHT: usize = 900;
+
+//part of the full frame, normalized with (0, 0) at the top left corner of the saved image
+#[derive(Clone, Copy)]
+pub struct CropWindow {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl CropWindow {
+    pub fn new(x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        Self {
+            x0: (clamp(x0.min(x1), 0.0, 1.0)),
+            y0: (clamp(y0.min(y1), 0.0, 1.0)),
+            x1: (clamp(x0.max(x1), 0.0, 1.0)),
+            y1: (clamp(y0.max(y1), 0.0, 1.0)),
+        }
+    }
+
+    //the same window in pixels of a full frame of width x height
+    pub fn pixels(x: usize, y: usize, w: usize, h: usize, width: usize, height: usize) -> Self {
+        Self::new(
+            x as f64 / width as f64,
+            y as f64 / height as f64,
+            (x + w) as f64 / width as f64,
+            (y + h) as f64 / height as f64,
+        )
+    }
+
+    //first and one past the last column and row, at least one pixel wide and high
+    pub fn bounds(&self, width: usize, height: usize) -> (usize, usize, usize, usize) {
+        let x_begin = ((self.x0 * width as f64) as usize).min(width - 1);
+        let y_begin = ((self.y0 * height as f64) as usize).min(height - 1);
+        let x_end = ((self.x1 * width as f64).ceil() as usize).clamp(x_begin + 1, width);
+        let y_end = ((self.y1 * height as f64).ceil() as usize).clamp(y_begin + 1, height);
+        (x_begin, x_end, y_begin, y_end)
+    }
+}
 
-    let camera = Camera::whale();
-    let world = HittableList::whale();
-    let lamp = Arc::new(HittableList::whale_lights());
+#[derive(Clone)]
+pub enum RegionOutput {
+    Cropped,           //only the window, at its own size
+    Composite(String), //pasted over an earlier full frame render loaded from this path
+}
+
+//re-renders one window of the frame, usually at a higher sample count to clean up a noisy area
+#[derive(Clone)]
+pub struct RenderRegion {
+    pub window: CropWindow,
+    pub samples_per_pixel: usize,
+    pub output: RegionOutput,
+}
 
-    let bvhworld = BVHNode::new(world.objects.clone(), 0, world.objects.len(), 0.0, 1.0);
+impl RenderRegion {
+    pub fn new(window: CropWindow, samples_per_pixel: usize, output: RegionOutput) -> Self {
+        Self {
+            window: (window),
+            samples_per_pixel: (samples_per_pixel),
+            output: (output),
+        }
+    }
+}
+
+//the cameras to render, built from the scene's main camera
+pub type ViewSet = fn(&Camera) -> Vec<Camera>;
+
+//how several views of one scene end up on disk
+#[derive(Clone, Copy)]
+pub enum ViewLayout {
+    Separate,   //one file per view, path_0.jpg, path_1.jpg, ...
+    SideBySide, //left to right in one file, the usual layout for stereo pairs
+    OverUnder,  //top to bottom in one file, the usual layout for stereo panoramas
+}
+
+fn render_frame(
+    camera: &Camera,
+    bvhworld: &BVHNode,
+    lamp: &Arc<HittableList>,
+    delta_lamp: &Arc<LightList>,
+    background: &Arc<dyn Background>,
+    region: Option<&RenderRegion>,
+    path: &str,
+) {
+    let img = render_image(camera, bvhworld, lamp, delta_lamp, background, region);
+    match region {
+        Some(RenderRegion {
+            window,
+            output: RegionOutput::Composite(base),
+            ..
+        }) => {
+            //the untouched pixels come from an earlier full frame render
+            let mut full = image::open(base).unwrap().to_rgb8();
+            let width = (camera.aspect_ratio() * HEIGHT as f64) as usize;
+            if full.width() as usize != width || full.height() as usize != HEIGHT {
+                println!(
+                    "{}",
+                    style("Base image size differs, saving the region alone.").red()
+                );
+                save_image(img, path);
+                return;
+            }
+            let (x_begin, _, y_begin, _) = window.bounds(width, HEIGHT);
+            image::imageops::replace(&mut full, &img, x_begin as u32, y_begin as u32);
+            save_image(full, path);
+        }
+        _ => save_image(img, path),
+    }
+}
+
+//every camera sees the same BVH, it is only built once
+fn render_views(
+    cameras: &[Camera],
+    bvhworld: &BVHNode,
+    lamp: &Arc<HittableList>,
+    delta_lamp: &Arc<LightList>,
+    background: &Arc<dyn Background>,
+    layout: ViewLayout,
+    path: &str,
+) {
+    let mut images = Vec::new();
+    for (i, camera) in cameras.iter().enumerate() {
+        println!("View {} of {}", i + 1, cameras.len());
+        images.push(render_image(
+            camera, bvhworld, lamp, delta_lamp, background, None,
+        ));
+    }
+    match layout {
+        ViewLayout::Separate => {
+            let (stem, extension) = match path.rfind('.') {
+                Some(dot) => path.split_at(dot),
+                None => (path, ""),
+            };
+            for (i, img) in images.into_iter().enumerate() {
+                save_image(img, &format!("{}_{}{}", stem, i, extension));
+            }
+        }
+        ViewLayout::SideBySide | ViewLayout::OverUnder => {
+            let across = matches!(layout, ViewLayout::SideBySide);
+            let width = images.iter().map(|img| img.width());
+            let height = images.iter().map(|img| img.height());
+            let (width, height) = if across {
+                (width.sum(), height.max().unwrap_or(0))
+            } else {
+                (width.max().unwrap_or(0), height.sum())
+            };
+            let mut output: RgbImage = ImageBuffer::new(width, height);
+            let mut offset = 0;
+            for img in &images {
+                for (x, y, pixel) in img.enumerate_pixels() {
+                    if across {
+                        output.put_pixel(x + offset, y, *pixel);
+                    } else {
+                        output.put_pixel(x, y + offset, *pixel);
+                    }
+                }
+                offset += if across { img.width() } else { img.height() };
+            }
+            save_image(output, path);
+        }
+    }
+}
+
+//the width follows the camera's aspect ratio, with a region only its window is traced and returned
+fn render_image(
+    camera: &Camera,
+    bvhworld: &BVHNode,
+    lamp: &Arc<HittableList>,
+    delta_lamp: &Arc<LightList>,
+    background: &Arc<dyn Background>,
+    region: Option<&RenderRegion>,
+) -> RgbImage {
+    let width = (camera.aspect_ratio() * HEIGHT as f64) as usize;
+    let mut samples_per_pixel = 1000;
+    let max_depth = 50;
 
     println!(
-        "Image size: {}\nJPEG quality: {}",
-        style(WIDTH.to_string() + "x" + &HEIGHT.to_string()).yellow(),
-        style(quality.to_string()).yellow(),
+        "Image size: {}",
+        style(width.to_string() + "x" + &HEIGHT.to_string()).yellow(),
     );
 
+    let (x_begin, x_end, y_begin, y_end) = match region {
+        Some(region) => {
+            samples_per_pixel = region.samples_per_pixel;
+            region.window.bounds(width, HEIGHT)
+        }
+        None => (0, width, 0, HEIGHT),
+    };
+    let crop_width = x_end - x_begin;
+    let crop_height = y_end - y_begin;
+    if region.is_some() {
+        println!(
+            "Render region: {} at ({}, {})",
+            style(crop_width.to_string() + "x" + &crop_height.to_string()).yellow(),
+            x_begin,
+            y_begin,
+        );
+    }
+
     println!("Sample per pixel: {}", samples_per_pixel);
     // Create image data
-    let mut img: RgbImage = ImageBuffer::new(WIDTH as u32, HEIGHT as u32);
+    let mut img: RgbImage = ImageBuffer::new(crop_width as u32, crop_height as u32);
 
     let multiprogress = Arc::new(MultiProgress::new());
     multiprogress.set_move_cursor(true);
@@ -99,10 +526,10 @@ pub fn render() {
     let thread_total = 8;
     let mut threads = Vec::new();
     let mut output_pixel = Vec::new();
-    let hight_line = HEIGHT / thread_total;
+    let hight_line = crop_height / thread_total;
 
     let mut random_pixal = Vec::default();
-    let sum = WIDTH * HEIGHT;
+    let sum = crop_width * crop_height;
     for i in 0..sum {
         random_pixal.push(i);
     }
@@ -113,22 +540,26 @@ pub fn render() {
         let hight_begin = hight_line * thread_num;
         let mut hight_end = hight_begin + hight_line;
         if thread_num == thread_total - 1 {
-            hight_end = HEIGHT;
+            hight_end = crop_height;
         }
 
         let world_thread = bvhworld.clone();
-        let camera_thread = camera;
+        let camera_thread = camera.clone();
 
         let t_random_pixel = random_pixal.clone();
 
         let mp = multiprogress.clone();
-        let progress_bar = mp.add(ProgressBar::new(((hight_end - hight_begin) * WIDTH) as u64));
+        let progress_bar = mp.add(ProgressBar::new(
+            ((hight_end - hight_begin) * crop_width) as u64,
+        ));
         progress_bar.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] [{pos}/{len}] ({eta})")
         .progress_chars("#>-"));
 
         let (tx, rx) = channel();
         let light = lamp.clone();
+        let delta_lights = delta_lamp.clone();
+        let background_thread = background.clone();
 
         threads.push((
             thread::spawn(move || {
@@ -139,19 +570,28 @@ pub fn render() {
                 let mut pixel_color_thread = Vec::new();
 
                 for y in hight_begin..hight_end {
-                    for x in 0..WIDTH {
-                        let cnt = y * WIDTH + x;
+                    for x in 0..crop_width {
+                        let cnt = y * crop_width + x;
                         let map_cnt = t_random_pixel[cnt as usize];
-                        let x_map = map_cnt % WIDTH;
-                        let y_map = map_cnt / WIDTH;
+                        //rows of the full frame count up from the bottom
+                        let x_map = x_begin + map_cnt % crop_width;
+                        let y_map = HEIGHT - y_end + map_cnt / crop_width;
                         let mut col = Vec3::new(0.0, 0.0, 0.0);
                         for _s in 0..samples_per_pixel {
-                            let u = (x_map as f64 + random_double()) / (WIDTH as f64);
+                            let u = (x_map as f64 + random_double()) / (width as f64);
                             let v = (y_map as f64 + random_double()) / (HEIGHT as f64);
                             let r = camera_thread.get_ray(u, v);
-                            col += ray_color(&r, v, &world_thread, light.clone(), max_depth);
+                            col += ray_color(
+                                &r,
+                                background_thread.clone(),
+                                &world_thread,
+                                light.clone(),
+                                &delta_lights,
+                                None,
+                                max_depth,
+                            );
                         }
-                        col = col / samples_per_pixel as f64;
+                        col = col * camera_thread.exposure / samples_per_pixel as f64;
                         let mut r = col.x;
                         let mut g = col.y;
                         let mut b = col.z;
@@ -202,36 +642,90 @@ pub fn render() {
     }
     println!("Generating Image...");
 
-    let mut image_output = [[[0; 3]; HEIGHT]; WIDTH];
+    let mut image_output = vec![vec![[0; 3]; crop_height]; crop_width];
     let mut pixel_num = 0;
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
+    for y in 0..crop_height {
+        for x in 0..crop_width {
             let pixel_color = output_pixel[pixel_num];
-            let cnt = y * WIDTH + x;
+            let cnt = y * crop_width + x;
             let map_cnt = random_pixal[cnt as usize];
-            let y_map = map_cnt / WIDTH;
-            let x_map = map_cnt % WIDTH;
+            let y_map = map_cnt / crop_width;
+            let x_map = map_cnt % crop_width;
             image_output[x_map][y_map] = pixel_color;
             pixel_num += 1
         }
     }
 
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
+    for y in 0..crop_height {
+        for x in 0..crop_width {
             let pixel_color = image_output[x as usize][y as usize];
-            let pixel = img.get_pixel_mut(x as u32, (HEIGHT - y - 1) as u32);
+            let pixel = img.get_pixel_mut(x as u32, (crop_height - y - 1) as u32);
             *pixel = image::Rgb(pixel_color);
         }
     }
 
+    img
+}
+
+fn save_image(img: RgbImage, path: &str) {
+    let quality = 100; // From 0 to 100
+    if !path.ends_with(".png") {
+        println!("JPEG quality: {}", style(quality.to_string()).yellow());
+    }
     // Output image to file
     println!("Ouput image as \"{}\"", style(path).yellow());
     let output_image = image::DynamicImage::ImageRgb8(img);
     let mut output_file = File::create(path).unwrap();
-    match output_image.write_to(&mut output_file, image::ImageOutputFormat::Jpeg(quality)) {
+    let format = if path.ends_with(".png") {
+        image::ImageOutputFormat::Png
+    } else {
+        image::ImageOutputFormat::Jpeg(quality)
+    };
+    match output_image.write_to(&mut output_file, format) {
         Ok(_) => {}
         // Err(_) => panic!("Outputting image fails."),
         Err(_) => println!("{}", style("Outputting image fails.").red()),
     }
-    exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic_tools::vec3::Point;
+    use crate::hittable::xy_rectangle::Cube;
+    use crate::material::subsurface::Subsurface;
+
+    #[test]
+    fn white_furnace_subsurface_slab() {
+        //a non-absorbing, index-matched slab under a white sky neither adds nor loses light,
+        //however many times a path scatters inside it
+        let mut world = HittableList::new();
+        world.add(Arc::new(Cube::new(
+            Point::new(-50.0, -50.0, 0.0),
+            Point::new(50.0, 50.0, 2.0),
+            Subsurface::new_albedo(1.0, Color::new(1.0, 1.0, 1.0), 0.25, 0.0),
+        )));
+        let background: Arc<dyn Background> =
+            Arc::new(SolidBackground::new(Color::new(1.0, 1.0, 1.0)));
+        let light = Arc::new(HittableList::new());
+        let delta_lights = LightList::new();
+        let samples = 2000;
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _i in 0..samples {
+            let r = Ray::new(Point::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+            sum += ray_color(
+                &r,
+                background.clone(),
+                &world,
+                light.clone(),
+                &delta_lights,
+                None,
+                10,
+            );
+        }
+        let mean = sum / samples as f64;
+        assert!((mean.x - 1.0).abs() < 0.03, "mean {}", mean.x);
+        assert!((mean.y - 1.0).abs() < 0.03, "mean {}", mean.y);
+        assert!((mean.z - 1.0).abs() < 0.03, "mean {}", mean.z);
+    }
 }
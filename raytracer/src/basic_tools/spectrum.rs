@@ -0,0 +1,71 @@
+use super::vec3::{Color, Vec3};
+
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+
+fn gaussian(x: f64, mu: f64, sigma1: f64, sigma2: f64) -> f64 {
+    let sigma = if x < mu { sigma1 } else { sigma2 };
+    (-0.5 * ((x - mu) / sigma).powi(2)).exp()
+}
+
+//CIE 1931 color matching functions, multi-lobe fit by Wyman, Sloan and Shirley
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    Vec3::new(
+        1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+        0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+        1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+//linear sRGB, D65 white
+pub fn xyz_to_rgb(xyz: &Vec3) -> Color {
+    Color::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+//integrals of xyz_to_rgb(cie_xyz(lambda)) over [LAMBDA_MIN, LAMBDA_MAX]
+const RGB_INTEGRAL: [f64; 3] = [128.36268551, 101.54863599, 97.04955563];
+
+//hero wavelength for a path, uniform over the visible range
+pub fn sample_wavelength(u: f64) -> f64 {
+    LAMBDA_MIN + u * (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+//rgb weight of a path traced at a single wavelength divided by its pdf,
+//averages to white over many wavelengths
+pub fn wavelength_weight(lambda: f64) -> Color {
+    let rgb = xyz_to_rgb(&cie_xyz(lambda)) * (LAMBDA_MAX - LAMBDA_MIN);
+    Color::new(
+        rgb.x / RGB_INTEGRAL[0],
+        rgb.y / RGB_INTEGRAL[1],
+        rgb.z / RGB_INTEGRAL[2],
+    )
+}
+
+//spectral radiance of a black body, lambda in nm
+pub fn planck(lambda: f64, kelvin: f64) -> f64 {
+    let c = 299792458.0;
+    let h = 6.62606957e-34;
+    let kb = 1.3806488e-23;
+    let l = lambda * 1e-9;
+    2.0 * h * c * c / (l.powi(5) * ((h * c / (l * kb * kelvin)).exp() - 1.0))
+}
+
+//color of a black body at the given temperature, scaled to unit luminance
+pub fn blackbody_color(kelvin: f64) -> Color {
+    let mut xyz = Vec3::new(0.0, 0.0, 0.0);
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        xyz += cie_xyz(lambda) * planck(lambda, kelvin);
+        lambda += 5.0;
+    }
+    if xyz.y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let rgb = xyz_to_rgb(&(xyz / xyz.y));
+    Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
@@ -107,6 +107,10 @@ impl Vec3 {
         p
     }
 
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
     pub fn random_to_sphere(radius: f64, distance_squared: f64) -> Vec3 {
         let r1 = random_double();
         let r2 = random_double();
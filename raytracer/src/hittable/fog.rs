@@ -94,6 +94,8 @@ impl<T: Texture> Hittable for ConstantMedium<T> {
         rec.normal = Vec3::new(0.0, 0.0, 0.0);
         rec.front_face = true;
         rec.mat_ptr = Some(&self.phase_function);
+        rec.tangent = Vec3::default();
+        rec.bitangent = Vec3::default();
 
         true
     }
@@ -1,6 +1,7 @@
 use super::super::material::{
-    dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
-    mixmaterial::MixtureMaterial,
+    conductor::Conductor, dielectric::Dielectric, diffuse_light::DiffuseLight,
+    lambertian::Lambertian, metal::Metal, mixmaterial::MixtureMaterial, oren_nayar::OrenNayar,
+    rough_dielectric::RoughDielectric, subsurface::Subsurface,
 };
 use super::aabb::AABB;
 use super::hittable_origin::random_double;
@@ -8,6 +9,7 @@ use super::ring::Ring;
 use super::triangle::Object;
 use super::{
     super::basic_tools::{
+        matrix::Matrix4,
         ray::Ray,
         vec3::{Color, Point, Vec3},
     },
@@ -17,7 +19,11 @@ use super::{
     bvh::BVHNode,
     // fog::ConstantMedium,
     hittable_origin::{random_t, HitRecord, Hittable},
+    normal_map::BumpMap,
+    pdf::Distribution1D,
     sphere::Sphere,
+    tlas::{build_blas, TLAS},
+    transform::Transform,
     xy_rectangle::{
         Cube, FlipFace, RotateX, RotateY, Translate, XYRectangle, XZRectangle, YZRectangle,
     },
@@ -26,6 +32,35 @@ use super::{
 use crate::texture::text::ImageTexture;
 use std::sync::Arc;
 
+//most times a ray can cross a surface before the rest is ignored in area_pdf_value
+const MAX_CROSSINGS: usize = 64;
+
+//pdf of a surface whose parts are picked in proportion to their area and then sampled uniformly,
+//each crossing along the ray adds distance^2 / (cosine * total_area); the crossings are found one
+//hit at a time, so with a BVH as the surface only the parts along the ray are tested
+pub fn area_pdf_value(surface: &dyn Hittable, total_area: f64, o: &Point, v: &Vec3) -> f64 {
+    if total_area <= 0.0 {
+        return 0.0;
+    }
+    let r = Ray::new(*o, *v, 0.0);
+    let mut t_min = 0.001;
+    let mut pdf = 0.0;
+    for _i in 0..MAX_CROSSINGS {
+        let mut rec = HitRecord::default();
+        if !surface.hit(&r, t_min, f64::INFINITY, &mut rec) {
+            break;
+        }
+        let distance_squared = rec.t * rec.t * v.length_squared();
+        let cosine = (Vec3::dot(v, &rec.normal) / v.length()).abs();
+        if cosine > 0.0 {
+            pdf += distance_squared / (cosine * total_area);
+        }
+        //only just past the hit, two crossings close together both count
+        t_min = rec.t * (1.0 + 1e-9);
+    }
+    pdf
+}
+
 #[derive(Clone, Default)]
 pub struct HittableList {
     pub objects: Vec<Arc<dyn Hittable>>,
@@ -46,6 +81,25 @@ impl HittableList {
         }
     }
 
+    //pdf of picking a child with the probability given by weights and then sampling it,
+    //tests every child, area_pdf_value is cheaper when the children are picked by area
+    pub fn weighted_pdf_value(&self, weights: &Distribution1D, o: &Point, v: &Vec3) -> f64 {
+        let mut sum = 0.0;
+        for (i, object) in self.objects.iter().enumerate() {
+            let weight = weights.discrete_pdf(i);
+            if weight > 0.0 {
+                sum += weight * object.pdf_value(o, v);
+            }
+        }
+        sum
+    }
+
+    pub fn weighted_random(&self, weights: &Distribution1D, o: &Vec3) -> Vec3 {
+        let mut pdf = 0.0;
+        let index = weights.sample_discrete(random_double(), &mut pdf);
+        self.objects[index].random(o)
+    }
+
     pub fn whale_lights() -> HittableList {
         let mut lights = HittableList::default();
         let light = DiffuseLight::new_col(Color::new(1.0, 1.0, 1.0), 30.0);
@@ -55,6 +109,45 @@ impl HittableList {
     }
 
     pub fn whale() -> HittableList {
+        let mut objects = HittableList::whale_stage();
+        let (mut instances, _whale) = HittableList::whale_instances();
+        instances.rebuild();
+        objects.add(Arc::new(instances));
+        objects
+    }
+
+    //the meshes of the whale scene and the index of the whale among them
+    pub fn whale_instances() -> (TLAS, usize) {
+        let glass = Dielectric::new(1.5);
+        let frosted = RoughDielectric::new(1.5, 0.3);
+        let cloud = Arc::new(Object::new(&String::from("obj/cloud.obj"), frosted, 0.6));
+        let cloud = build_blas(&cloud);
+
+        //meshes are placed through one top level structure
+        let mut instances = TLAS::new();
+        instances.add(
+            cloud.clone(),
+            Matrix4::translate(Vec3::new(0.0, 300.0, 300.0)),
+        );
+        //same triangles, shaded as clear glass
+        instances.add_material(
+            cloud,
+            Matrix4::translate(Vec3::new(500.0, 600.0, 400.0)),
+            Arc::new(glass),
+        );
+
+        let blue = Subsurface::new_albedo(1.33, Color::new(0.55, 0.75, 0.95), 15.0, 0.3);
+
+        let obj = Arc::new(Object::new(&String::from("obj/whale.obj"), blue, 800.0));
+        let whale = instances.add(
+            build_blas(&obj),
+            Matrix4::translate(Vec3::new(300.0, 350.0, 400.0)) * Matrix4::rotate_y(180.0),
+        );
+        (instances, whale)
+    }
+
+    //everything in the whale scene except the instances
+    pub fn whale_stage() -> HittableList {
         let mut objects = HittableList::default();
 
         let back = ImageTexture::new(&String::from("pinkblue.png"));
@@ -62,7 +155,7 @@ impl HittableList {
         //  let white = Lambertian::new(Color::new(0.73, 0.73, 0.73));
         let light = DiffuseLight::new_col(Color::new(1.0, 1.0, 1.0), 25.0);
         let mut boxes1 = HittableList::default();
-        let aluminum = Metal::new(Vec3::new(0.8, 0.85, 0.88), 0.);
+        let aluminum = Conductor::aluminum(0.05);
 
         let boxes_per_side = 20;
         for i in 0..boxes_per_side {
@@ -92,25 +185,6 @@ impl HittableList {
 
         let glass = Dielectric::new(1.5);
 
-        let cloud = Arc::new(Object::new(
-            &String::from("obj/cloud.obj"),
-            glass.clone(),
-            0.6,
-        ));
-        let cloud = Arc::new(BVHNode::new(
-            cloud.surface.clone().objects,
-            0,
-            cloud.surface.objects.len(),
-            0.0,
-            1.0,
-        ));
-
-        let move_obj = Arc::new(Translate::new(cloud.clone(), Vec3::new(0.0, 300.0, 300.0)));
-        objects.add(move_obj);
-
-        let move_obj = Arc::new(Translate::new(cloud, Vec3::new(500.0, 600.0, 400.0)));
-        objects.add(move_obj);
-
         /*  objects.add(Arc::new(YZRectangle::new(
             0.0,
             1300.0,
@@ -141,22 +215,6 @@ impl HittableList {
         objects.add(Arc::new(XYRectangle::new(
             -2000.0, 3000.0, 0.0, 1300.0, 1355.0, pink,
         )));
-        let light2 = DiffuseLight::new_col(Color::new(1.0, 1.0, 1.0), 1.0);
-        let blue = MixtureMaterial::new(light2, glass.clone(), 0.5);
-
-        let obj = Arc::new(Object::new(&String::from("obj/whale.obj"), blue, 800.0));
-        let bvh_obj = Arc::new(BVHNode::new(
-            obj.surface.clone().objects,
-            0,
-            obj.surface.objects.len(),
-            0.0,
-            1.0,
-        ));
-        let move_obj = Arc::new(RotateY::new(bvh_obj, 180.0));
-        let move_obj = Arc::new(Translate::new(move_obj, Vec3::new(300.0, 350.0, 400.0)));
-
-        objects.add(move_obj);
-
         let center = Vec3::new(300.0, 300.0, 400.0);
 
         let mut planets_ring = HittableList::default();
@@ -222,10 +280,15 @@ impl HittableList {
             70.0,
             jupitermat,
         ));
+        let jupiter = Arc::new(BumpMap::new(
+            jupiter,
+            ImageTexture::new(&String::from("Jupiter.jpg")),
+            0.5,
+        ));
         let jupiter = Arc::new(RotateX::new(jupiter, 20.0));
         objects.add(jupiter);
 
-        let saturnmat = Lambertian::newp(ImageTexture::new(&String::from("Saturn.jpg")));
+        let saturnmat = OrenNayar::newp(ImageTexture::new(&String::from("Saturn.jpg")), 20.0);
         let saturn = Arc::new(Sphere::new(
             Point::new(-50.0, 350.0, 350.0),
             60.0,
@@ -233,9 +296,8 @@ impl HittableList {
         ));
         let saturn = Arc::new(RotateX::new(saturn, 20.0));
         objects.add(saturn);
-        let light =
-            DiffuseLight::new_col(Color::new(245.0 / 255.0, 222.0 / 255.0, 179.0 / 255.0), 1.0);
-        let transcu = MixtureMaterial::new(light, glass, 0.6);
+        let wheat = Color::new(245.0 / 255.0, 222.0 / 255.0, 179.0 / 255.0);
+        let transcu = Subsurface::new_slab(1.5, wheat, 2.0, 0.0, 4.0);
         let saturn_ring = Arc::new(Ring::new(
             Point::new(-50.0, 350.0, 350.0),
             100.0,
@@ -254,6 +316,24 @@ impl HittableList {
         objects.add(saturn_ring);
         objects
     }
+    pub fn outdoor_whale() -> HittableList {
+        let mut objects = HittableList::default();
+
+        let ground = Lambertian::new(Color::new(0.45, 0.42, 0.38));
+        objects.add(Arc::new(XZRectangle::new(
+            -5000.0, 5000.0, -5000.0, 5000.0, 0.0, ground,
+        )));
+
+        let white = Lambertian::new(Color::new(0.73, 0.73, 0.73));
+        let obj = Arc::new(Object::new(&String::from("obj/whale.obj"), white, 800.0));
+        let move_obj = Arc::new(Transform::new(
+            build_blas(&obj),
+            Matrix4::translate(Vec3::new(300.0, 250.0, 400.0)) * Matrix4::rotate_y(180.0),
+        ));
+        objects.add(move_obj);
+        objects
+    }
+
     pub fn cornell_box() -> HittableList {
         let mut objects = HittableList::default();
 
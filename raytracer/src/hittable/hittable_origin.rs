@@ -11,6 +11,8 @@ pub struct HitRecord<'a> {
     pub v: f64,
     pub front_face: bool,
     pub mat_ptr: Option<&'a dyn Material>,
+    pub tangent: Vec3,   //dp/du, zero when the surface has no uv parametrization
+    pub bitangent: Vec3, //dp/dv
 }
 
 impl<'a> HitRecord<'a> {
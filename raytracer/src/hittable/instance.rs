@@ -0,0 +1,62 @@
+use super::super::basic_tools::{
+    matrix::Matrix4,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use super::super::material::metal::Material;
+use super::aabb::AABB;
+use super::hittable_origin::{HitRecord, Hittable};
+use super::transform::Transform;
+use std::sync::Arc;
+
+//one placement of shared geometry, usually a BVHNode built once; copies only cost
+//a matrix and an Arc, and may shade the triangles with their own material
+#[derive(Clone)]
+pub struct Instance {
+    pub transform: Transform,
+    pub material: Option<Arc<dyn Material>>, //overrides the material baked into the geometry
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn Hittable>, matrix: Matrix4) -> Self {
+        Self {
+            transform: (Transform::new(geometry, matrix)),
+            material: None,
+        }
+    }
+
+    pub fn new_material(
+        geometry: Arc<dyn Hittable>,
+        matrix: Matrix4,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            transform: (Transform::new(geometry, matrix)),
+            material: Some(material),
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        self.transform.bounding_box(time0, time1, output_box)
+    }
+
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if !self.transform.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+        if let Some(material) = &self.material {
+            rec.mat_ptr = Some(material.as_ref());
+        }
+        true
+    }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.transform.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.transform.random(o)
+    }
+}
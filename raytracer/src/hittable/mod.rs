@@ -3,9 +3,13 @@ pub mod bvh;
 pub mod fog;
 pub mod hittable_list;
 pub mod hittable_origin;
+pub mod instance;
 pub mod moving_sphere;
+pub mod normal_map;
 pub mod pdf;
 pub mod ring;
 pub mod sphere;
+pub mod tlas;
+pub mod transform;
 pub mod triangle;
 pub mod xy_rectangle;
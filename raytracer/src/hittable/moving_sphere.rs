@@ -1,10 +1,11 @@
 use super::super::basic_tools;
-use super::super::material::metal::Material;
+use super::super::material::metal::{Material, ONB};
 use super::{
     aabb::AABB,
     hittable_origin::{HitRecord, Hittable},
 };
 use basic_tools::{ray::Ray, vec3::Point, vec3::Vec3};
+use std::f64::consts::PI;
 #[derive(Clone, Default)]
 pub struct MovingSphere<M>
 where
@@ -62,6 +63,8 @@ impl<M: Material> Hittable for MovingSphere<M> {
             v: 0.0,
             front_face: bool::default(),
             mat_ptr: Some(&self.mat_ptr),
+            tangent: Vec3::default(),
+            bitangent: Vec3::default(),
         };
         let outward_normal = (rec.p - self.center(r.time)) / self.radius;
         rec.set_face_normal(r, &outward_normal);
@@ -80,4 +83,28 @@ impl<M: Material> Hittable for MovingSphere<M> {
         *output_box = AABB::surrounding_box(box0, box1);
         true
     }
+
+    //pdf_value and random carry no time, so both aim at the center in the middle of the shutter
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let center = self.center((self.time0 + self.time1) / 2.0);
+        let distance_squared = (center - *o).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 0.0;
+        }
+        let cos_theta_max = (1.0 - self.radius.powi(2) / distance_squared).sqrt();
+        let cos_theta = Vec3::dot(&Vec3::unit_vector(*v), &Vec3::unit_vector(center - *o));
+        if cos_theta < cos_theta_max {
+            return 0.0;
+        }
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        let direction = self.center((self.time0 + self.time1) / 2.0) - *o;
+        let distance_squared = direction.length_squared();
+        let mut uvw = ONB::default();
+        uvw.build_from_w(direction);
+        uvw.local_vec(Vec3::random_to_sphere(self.radius, distance_squared))
+    }
 }
@@ -0,0 +1,159 @@
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use super::aabb::AABB;
+use super::hittable_origin::{HitRecord, Hittable};
+use crate::texture::text::Texture;
+use std::sync::Arc;
+
+//uv step used for the finite differences of a bump map
+const BUMP_DELTA: f64 = 0.0005;
+
+//replace the normal with a shading normal given facing away from the surface,
+//unless it would turn away from the viewer
+fn set_shading_normal(r: &Ray, rec: &mut HitRecord, outward: Vec3) {
+    let normal = if rec.front_face { outward } else { -outward };
+    if Vec3::dot(&normal, &r.direct) < 0.0 {
+        rec.normal = normal;
+    }
+}
+
+fn outward_normal(rec: &HitRecord) -> Vec3 {
+    if rec.front_face {
+        rec.normal
+    } else {
+        -rec.normal
+    }
+}
+
+//tangent space normal map, rgb in [0,1] maps to xyz in [-1,1] with z along the normal
+#[derive(Clone)]
+pub struct NormalMap<T>
+where
+    T: Texture,
+{
+    pub ptr: Arc<dyn Hittable>,
+    pub map: T,
+    pub strength: f64,
+}
+
+impl<T: Texture> NormalMap<T> {
+    pub fn new(p: Arc<dyn Hittable>, map: T, strength: f64) -> Self {
+        Self {
+            ptr: (p),
+            map: (map),
+            strength: (strength),
+        }
+    }
+}
+
+impl<T: Texture> Hittable for NormalMap<T> {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if !self.ptr.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+        if rec.tangent.near_zero() {
+            return true;
+        }
+        let n = outward_normal(rec);
+        let t = Vec3::unit_vector(rec.tangent - n * Vec3::dot(&n, &rec.tangent));
+        let mut b = Vec3::cross(n, t);
+        if Vec3::dot(&b, &rec.bitangent) < 0.0 {
+            b = -b;
+        }
+        let c = self.map.value(rec.u, rec.v, &rec.p);
+        let shading = t * ((2.0 * c.x - 1.0) * self.strength)
+            + b * ((2.0 * c.y - 1.0) * self.strength)
+            + n * (2.0 * c.z - 1.0).max(0.0);
+        if shading.near_zero() {
+            return true;
+        }
+        set_shading_normal(r, rec, Vec3::unit_vector(shading));
+        true
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        self.ptr.bounding_box(time0, time1, output_box)
+    }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.ptr.random(o)
+    }
+}
+
+//height field along the normal, the first channel of the texture times scale
+#[derive(Clone)]
+pub struct BumpMap<T>
+where
+    T: Texture,
+{
+    pub ptr: Arc<dyn Hittable>,
+    pub height: T,
+    pub scale: f64,
+}
+
+impl<T: Texture> BumpMap<T> {
+    pub fn new(p: Arc<dyn Hittable>, height: T, scale: f64) -> Self {
+        Self {
+            ptr: (p),
+            height: (height),
+            scale: (scale),
+        }
+    }
+
+    fn displacement(&self, u: f64, v: f64, p: &Point) -> f64 {
+        self.height.value(u, v, p).x * self.scale
+    }
+}
+
+impl<T: Texture> Hittable for BumpMap<T> {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if !self.ptr.hit(r, t_min, t_max, rec) {
+            return false;
+        }
+        if rec.tangent.near_zero() || rec.bitangent.near_zero() {
+            return true;
+        }
+        let n = outward_normal(rec);
+        //shifting p along with uv keeps solid textures such as NoiseTexture working
+        let d = self.displacement(rec.u, rec.v, &rec.p);
+        let du = self.displacement(
+            rec.u + BUMP_DELTA,
+            rec.v,
+            &(rec.p + rec.tangent * BUMP_DELTA),
+        ) - d;
+        let dv = self.displacement(
+            rec.u,
+            rec.v + BUMP_DELTA,
+            &(rec.p + rec.bitangent * BUMP_DELTA),
+        ) - d;
+        let dpdu = rec.tangent + n * (du / BUMP_DELTA);
+        let dpdv = rec.bitangent + n * (dv / BUMP_DELTA);
+        let mut shading = Vec3::cross(dpdu, dpdv);
+        if shading.near_zero() {
+            return true;
+        }
+        if Vec3::dot(&shading, &n) < 0.0 {
+            shading = -shading;
+        }
+        set_shading_normal(r, rec, Vec3::unit_vector(shading));
+        true
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64, output_box: &mut AABB) -> bool {
+        self.ptr.bounding_box(time0, time1, output_box)
+    }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr.pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.ptr.random(o)
+    }
+}
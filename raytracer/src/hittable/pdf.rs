@@ -1,4 +1,9 @@
+use crate::light::background::Background;
 use crate::material::metal::ONB;
+use crate::material::microfacet::{
+    dielectric_pdf, gtr1_distribution, sample_dielectric, sample_gtr1, sample_visible_normal,
+    visible_pdf,
+};
 
 use super::super::basic_tools;
 use super::hittable_origin::{random_double, Hittable};
@@ -6,7 +11,7 @@ use basic_tools::{vec3::Point, vec3::Vec3};
 use std::f64::consts::PI;
 use std::sync::Arc;
 
-pub trait PDF {
+pub trait PDF: Send + Sync {
     fn value(&self, direction: &Vec3) -> f64;
     fn generate(&self) -> Vec3;
 }
@@ -47,6 +52,118 @@ impl PDF for CosinePDF {
     }
 }
 
+//reflection off GGX microfacets, sampled by visible normals
+#[derive(Clone, Copy, Default)]
+pub struct GGXPDF {
+    pub uvw: ONB,
+    pub wo: Vec3, //local direction towards the viewer
+    pub alpha: f64,
+}
+
+impl GGXPDF {
+    pub fn new(normal: Vec3, wo: Vec3, alpha: f64) -> Self {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(normal);
+        Self {
+            uvw: (uvw),
+            wo: (Vec3::unit_vector(uvw.to_local(wo))),
+            alpha: (alpha),
+        }
+    }
+}
+
+impl PDF for GGXPDF {
+    fn generate(&self) -> Vec3 {
+        let wm = sample_visible_normal(&self.wo, self.alpha, random_double(), random_double());
+        self.uvw.local_vec(Vec3::reflect(-self.wo, wm))
+    }
+
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = Vec3::unit_vector(self.uvw.to_local(*direction));
+        if wi.z <= 0.0 || self.wo.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = Vec3::unit_vector(self.wo + wi);
+        visible_pdf(&self.wo, &wm, self.alpha) / (4.0 * Vec3::dot(&self.wo, &wm).abs())
+    }
+}
+
+//reflection and refraction through a rough dielectric interface
+#[derive(Clone, Copy, Default)]
+pub struct RoughDielectricPDF {
+    pub uvw: ONB,
+    pub wo: Vec3,
+    pub alpha: f64,
+    pub eta: f64, //index on the far side over the index on the side of wo
+}
+
+impl RoughDielectricPDF {
+    pub fn new(normal: Vec3, wo: Vec3, alpha: f64, eta: f64) -> Self {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(normal);
+        Self {
+            uvw: (uvw),
+            wo: (Vec3::unit_vector(uvw.to_local(wo))),
+            alpha: (alpha),
+            eta: (eta),
+        }
+    }
+}
+
+impl PDF for RoughDielectricPDF {
+    fn generate(&self) -> Vec3 {
+        self.uvw.local_vec(sample_dielectric(
+            &self.wo,
+            self.alpha,
+            self.eta,
+            random_double(),
+            random_double(),
+            random_double(),
+        ))
+    }
+
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = Vec3::unit_vector(self.uvw.to_local(*direction));
+        dielectric_pdf(&self.wo, &wi, self.alpha, self.eta)
+    }
+}
+
+//reflection off the clearcoat, half vectors drawn from the GTR1 distribution
+#[derive(Clone, Copy, Default)]
+pub struct ClearcoatPDF {
+    pub uvw: ONB,
+    pub wo: Vec3,
+    pub alpha: f64,
+}
+
+impl ClearcoatPDF {
+    pub fn new(normal: Vec3, wo: Vec3, alpha: f64) -> Self {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(normal);
+        Self {
+            uvw: (uvw),
+            wo: (Vec3::unit_vector(uvw.to_local(wo))),
+            alpha: (alpha),
+        }
+    }
+}
+
+impl PDF for ClearcoatPDF {
+    fn generate(&self) -> Vec3 {
+        let wm = sample_gtr1(self.alpha, random_double(), random_double());
+        self.uvw.local_vec(Vec3::reflect(-self.wo, wm))
+    }
+
+    fn value(&self, direction: &Vec3) -> f64 {
+        let wi = Vec3::unit_vector(self.uvw.to_local(*direction));
+        if wi.z <= 0.0 || self.wo.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = Vec3::unit_vector(self.wo + wi);
+        gtr1_distribution(&wm, self.alpha) * wm.z / (4.0 * Vec3::dot(&self.wo, &wm).abs())
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct HittablePDF {
     pub o: Point,
@@ -99,3 +216,125 @@ impl PDF for MixturePDF {
         value
     }
 }
+
+//picks one of several pdfs in proportion to its weight
+#[derive(Clone, Default)]
+pub struct WeightedPDF {
+    pub pdfs: Vec<Arc<dyn PDF>>,
+    pub weights: Distribution1D,
+}
+
+impl WeightedPDF {
+    pub fn new(pdfs: Vec<Arc<dyn PDF>>, weights: Vec<f64>) -> Self {
+        Self {
+            pdfs: (pdfs),
+            weights: (Distribution1D::new(weights)),
+        }
+    }
+}
+
+impl PDF for WeightedPDF {
+    fn generate(&self) -> Vec3 {
+        let mut pdf = 0.0;
+        let i = self.weights.sample_discrete(random_double(), &mut pdf);
+        self.pdfs[i].generate()
+    }
+
+    fn value(&self, direction: &Vec3) -> f64 {
+        let mut value = 0.0;
+        for (i, p) in self.pdfs.iter().enumerate() {
+            let weight = self.weights.discrete_pdf(i);
+            if weight > 0.0 {
+                value += weight * p.value(direction);
+            }
+        }
+        value
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BackgroundPDF {
+    pub ptr: Option<Arc<dyn Background>>,
+}
+
+impl BackgroundPDF {
+    pub fn new(p: Arc<dyn Background>) -> Self {
+        Self { ptr: (Some(p)) }
+    }
+}
+
+impl PDF for BackgroundPDF {
+    fn generate(&self) -> Vec3 {
+        self.ptr.as_ref().unwrap().random()
+    }
+
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.ptr.as_ref().unwrap().pdf_value(direction)
+    }
+}
+
+//piecewise constant distribution over [0,1), used for importance sampling tables
+#[derive(Clone, Default)]
+pub struct Distribution1D {
+    pub func: Vec<f64>,
+    pub cdf: Vec<f64>,
+    pub func_int: f64,
+}
+
+impl Distribution1D {
+    pub fn new(f: Vec<f64>) -> Self {
+        let n = f.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 1..=n {
+            cdf[i] = cdf[i - 1] + f[i - 1].abs() / n as f64;
+        }
+        let func_int = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            *c = if func_int == 0.0 {
+                i as f64 / n as f64
+            } else {
+                *c / func_int
+            };
+        }
+        Self {
+            func: (f),
+            cdf: (cdf),
+            func_int: (func_int),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    fn find_interval(&self, u: f64) -> usize {
+        let index = self.cdf.partition_point(|c| *c <= u);
+        index.clamp(1, self.count()) - 1
+    }
+
+    //returns a value in [0,1) with its density, offset is the bucket it fell in
+    pub fn sample_continuous(&self, u: f64, pdf: &mut f64, offset: &mut usize) -> f64 {
+        let i = self.find_interval(u);
+        *offset = i;
+        let mut du = u - self.cdf[i];
+        if self.cdf[i + 1] - self.cdf[i] > 0.0 {
+            du /= self.cdf[i + 1] - self.cdf[i];
+        }
+        *pdf = if self.func_int > 0.0 {
+            self.func[i] / self.func_int
+        } else {
+            1.0
+        };
+        (i as f64 + du) / self.count() as f64
+    }
+
+    pub fn sample_discrete(&self, u: f64, pdf: &mut f64) -> usize {
+        let i = self.find_interval(u);
+        *pdf = self.discrete_pdf(i);
+        i
+    }
+
+    pub fn discrete_pdf(&self, index: usize) -> f64 {
+        self.cdf[index + 1] - self.cdf[index]
+    }
+}
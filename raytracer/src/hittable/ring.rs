@@ -1,8 +1,10 @@
 use super::super::basic_tools;
 use super::super::material::metal::Material;
 use super::aabb::AABB;
-use super::hittable_origin::{HitRecord, Hittable};
+use super::hittable_origin::{random_double, HitRecord, Hittable};
 use basic_tools::{ray::Ray, vec3::Point, vec3::Vec3};
+use std::f64::consts::PI;
+use std::f64::INFINITY;
 
 #[derive(Clone, Default)]
 pub struct Ring<M>
@@ -24,6 +26,10 @@ impl<M: Material> Ring<M> {
             mat: (mat_ptr),
         }
     }
+
+    pub fn area(&self) -> f64 {
+        PI * (self.rmax * self.rmax - self.rmin * self.rmin)
+    }
 }
 
 //whether hit the shpere t is the time
@@ -42,8 +48,10 @@ impl<M: Material> Hittable for Ring<M> {
 
         rec.p = p;
         rec.t = t;
-        rec.normal = Vec3::new(0.0, 1.0, 0.0);
+        rec.set_face_normal(r, &Vec3::new(0.0, 1.0, 0.0));
         rec.mat_ptr = Some(&self.mat);
+        rec.tangent = Vec3::default();
+        rec.bitangent = Vec3::default();
 
         true
     }
@@ -55,4 +63,30 @@ impl<M: Material> Hittable for Ring<M> {
         );
         true
     }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * v.length_squared();
+        let cosine = (v.y / v.length()).abs();
+
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        //uniform in area over the annulus
+        let r = (self.rmin * self.rmin
+            + random_double() * (self.rmax * self.rmax - self.rmin * self.rmin))
+            .sqrt();
+        let phi = 2.0 * PI * random_double();
+        let random_point = Point::new(
+            self.center.x + r * phi.cos(),
+            self.center.y,
+            self.center.z + r * phi.sin(),
+        );
+        random_point - *o
+    }
 }
@@ -32,6 +32,18 @@ pub fn get_sphere_uv(p: &Point, u: &mut f64, v: &mut f64) {
     *u = phi / (2.0 * PI);
     *v = theta / PI;
 }
+//derivatives of the point along u and v of get_sphere_uv, zero at the poles
+pub fn get_sphere_tangents(p: &Point, radius: f64, dpdu: &mut Vec3, dpdv: &mut Vec3) {
+    let sin_theta = (1.0 - p.y * p.y).max(0.0).sqrt();
+    if sin_theta < 1e-6 {
+        *dpdu = Vec3::default();
+        *dpdv = Vec3::default();
+        return;
+    }
+    *dpdu = Vec3::new(p.z, 0.0, -p.x) * (2.0 * PI * radius);
+    *dpdv = Vec3::new(-p.x * p.y / sin_theta, sin_theta, -p.y * p.z / sin_theta) * (PI * radius);
+}
+
 //whether hit the shpere t is the time
 impl<M: Material> Hittable for Sphere<M> {
     fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
@@ -59,10 +71,18 @@ impl<M: Material> Hittable for Sphere<M> {
             v: 1.0,
             front_face: bool::default(),
             mat_ptr: Some(&self.mat),
+            tangent: Vec3::default(),
+            bitangent: Vec3::default(),
         };
         let outward_normal = (rec.p - self.center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
         get_sphere_uv(&outward_normal, &mut rec.u, &mut rec.v);
+        get_sphere_tangents(
+            &outward_normal,
+            self.radius,
+            &mut rec.tangent,
+            &mut rec.bitangent,
+        );
         true
     }
 
@@ -77,7 +97,7 @@ impl<M: Material> Hittable for Sphere<M> {
     fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
         let mut rec = HitRecord::default();
         if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
-            return 1.0;
+            return 0.0;
         }
 
         let cos_thea_max = (1.0 - self.radius.powi(2) / (self.center - *o).length_squared()).sqrt();
@@ -0,0 +1,212 @@
+use super::super::basic_tools::{
+    matrix::Matrix4,
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use super::super::material::metal::Material;
+use super::aabb::AABB;
+use super::bvh::BVHNode;
+use super::hittable_origin::{random_int, HitRecord, Hittable};
+use super::instance::Instance;
+use super::transform::{Motion, Transform};
+use super::triangle::Object;
+use std::sync::Arc;
+
+//instances per top level leaf
+const TLAS_LEAF_SIZE: usize = 2;
+//deep enough for a balanced tree over any instance count that fits in memory
+const TLAS_STACK_SIZE: usize = 64;
+
+//bottom level structure, a mesh's BVH built once and shared by all of its instances;
+//a mesh already has one, its nodes are shared rather than built again
+pub fn build_blas(object: &Object) -> Arc<dyn Hittable> {
+    match &object.bvh {
+        Some(bvh) => Arc::new(bvh.clone()),
+        None => Arc::new(BVHNode::new(
+            object.surface.objects.clone(),
+            0,
+            object.surface.objects.len(),
+            0.0,
+            1.0,
+        )),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct TlasNode {
+    pub bbox: AABB,
+    pub first: usize, //first child node, or first entry of order for a leaf
+    pub count: usize, //instances in a leaf, 0 for inner nodes
+}
+
+//top level BVH over instances, stored flat and walked without recursion; moving
+//an instance only needs set_matrix and rebuild, the bottom level BVHs are kept
+#[derive(Clone, Default)]
+pub struct TLAS {
+    pub instances: Vec<Instance>,
+    pub bounds: Vec<AABB>,
+    pub order: Vec<usize>,
+    pub nodes: Vec<TlasNode>,
+}
+
+impl TLAS {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //returns the index of the instance, used by set_matrix
+    pub fn add(&mut self, blas: Arc<dyn Hittable>, matrix: Matrix4) -> usize {
+        self.instances.push(Instance::new(blas, matrix));
+        self.instances.len() - 1
+    }
+
+    pub fn add_material(
+        &mut self,
+        blas: Arc<dyn Hittable>,
+        matrix: Matrix4,
+        material: Arc<dyn Material>,
+    ) -> usize {
+        self.instances
+            .push(Instance::new_material(blas, matrix, material));
+        self.instances.len() - 1
+    }
+
+    pub fn set_matrix(&mut self, index: usize, matrix: Matrix4) {
+        let transform = &mut self.instances[index].transform;
+        *transform = Transform::new(transform.ptr.clone(), matrix);
+    }
+
+    //moving instance, the bounds cover the whole shutter
+    pub fn set_motion(&mut self, index: usize, motion: Motion) {
+        let transform = &mut self.instances[index].transform;
+        *transform = Transform::new_motion(transform.ptr.clone(), motion);
+    }
+
+    //only touches the instance bounds and the top level nodes, cheap enough for every frame
+    pub fn rebuild(&mut self) {
+        self.bounds = self
+            .instances
+            .iter()
+            .map(|instance| {
+                let mut bbox = AABB::default();
+                instance.bounding_box(0.0, 1.0, &mut bbox);
+                bbox
+            })
+            .collect();
+        self.order = (0..self.instances.len()).collect();
+        self.nodes.clear();
+        if self.instances.is_empty() {
+            return;
+        }
+        self.nodes.push(TlasNode::default());
+        self.build_node(0, 0, self.instances.len());
+    }
+
+    fn centroid(&self, index: usize, axis: usize) -> f64 {
+        let b = &self.bounds[index];
+        match axis {
+            0 => b.minimum.x + b.maximum.x,
+            1 => b.minimum.y + b.maximum.y,
+            _ => b.minimum.z + b.maximum.z,
+        }
+    }
+
+    //median split along the longest axis of the centroids
+    fn build_node(&mut self, node: usize, start: usize, end: usize) {
+        let mut bbox = self.bounds[self.order[start]];
+        for i in start + 1..end {
+            bbox = AABB::surrounding_box(bbox, self.bounds[self.order[i]]);
+        }
+        self.nodes[node].bbox = bbox;
+        if end - start <= TLAS_LEAF_SIZE {
+            self.nodes[node].first = start;
+            self.nodes[node].count = end - start;
+            return;
+        }
+
+        let mut low = [f64::MAX; 3];
+        let mut high = [f64::MIN; 3];
+        for i in start..end {
+            for axis in 0..3 {
+                let c = self.centroid(self.order[i], axis);
+                low[axis] = low[axis].min(c);
+                high[axis] = high[axis].max(c);
+            }
+        }
+        let mut axis = 0;
+        for a in 1..3 {
+            if high[a] - low[a] > high[axis] - low[axis] {
+                axis = a;
+            }
+        }
+        let mut order = std::mem::take(&mut self.order);
+        order[start..end].sort_by(|a, b| {
+            self.centroid(*a, axis)
+                .partial_cmp(&self.centroid(*b, axis))
+                .unwrap()
+        });
+        self.order = order;
+
+        let mid = start + (end - start) / 2;
+        let left = self.nodes.len();
+        self.nodes.push(TlasNode::default());
+        self.nodes.push(TlasNode::default());
+        self.nodes[node].first = left;
+        self.nodes[node].count = 0;
+        self.build_node(left, start, mid);
+        self.build_node(left + 1, mid, end);
+    }
+}
+
+impl Hittable for TLAS {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut stack = [0; TLAS_STACK_SIZE];
+        let mut top = 1;
+        let mut closest = t_max;
+        let mut hit_anything = false;
+        while top > 0 {
+            top -= 1;
+            let node = &self.nodes[stack[top]];
+            if !node.bbox.hit(r, t_min, closest) {
+                continue;
+            }
+            if node.count > 0 {
+                for i in node.first..node.first + node.count {
+                    if self.instances[self.order[i]].hit(r, t_min, closest, rec) {
+                        hit_anything = true;
+                        closest = rec.t;
+                    }
+                }
+            } else {
+                stack[top] = node.first;
+                stack[top + 1] = node.first + 1;
+                top += 2;
+            }
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        *output_box = self.nodes[0].bbox;
+        true
+    }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let weight = 1.0 / self.instances.len() as f64;
+        self.instances
+            .iter()
+            .map(|instance| instance.pdf_value(o, v) * weight)
+            .sum()
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        let index = random_int(0, self.instances.len() as i32 - 1);
+        self.instances[index as usize].random(o)
+    }
+}
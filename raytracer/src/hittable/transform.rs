@@ -0,0 +1,320 @@
+use super::super::basic_tools::{
+    matrix::{Matrix4, Pose},
+    ray::Ray,
+    vec3::{Point, Vec3},
+};
+use super::aabb::AABB;
+use super::hittable_origin::{clamp, HitRecord, Hittable};
+use std::f64::{consts::PI, INFINITY};
+use std::sync::Arc;
+
+//poses sampled across the shutter to bound a moving Transform
+const MOTION_STEPS: usize = 16;
+
+//a Transform moving from start at time0 to end at time1, held still outside of that
+#[derive(Clone, Copy)]
+pub struct Motion {
+    pub start: Pose,
+    pub end: Pose,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+impl Motion {
+    pub fn new(start: Pose, end: Pose, time0: f64, time1: f64) -> Self {
+        Self {
+            start: (start),
+            end: (end),
+            time0: (time0),
+            time1: (time1),
+        }
+    }
+
+    pub fn pose(&self, time: f64) -> Pose {
+        let t = if self.time1 > self.time0 {
+            clamp((time - self.time0) / (self.time1 - self.time0), 0.0, 1.0)
+        } else {
+            0.0
+        };
+        Pose::lerp(&self.start, &self.end, t)
+    }
+}
+
+fn transformed_box(bbox: &AABB, matrix: &Matrix4) -> AABB {
+    let mut min = Point::new(INFINITY, INFINITY, INFINITY);
+    let mut max = Point::new(-INFINITY, -INFINITY, -INFINITY);
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let x = i as f64 * bbox.maximum.x + (1 - i) as f64 * bbox.minimum.x;
+                let y = j as f64 * bbox.maximum.y + (1 - j) as f64 * bbox.minimum.y;
+                let z = k as f64 * bbox.maximum.z + (1 - k) as f64 * bbox.minimum.z;
+
+                let tester = matrix.transform_point(&Point::new(x, y, z));
+
+                min.x = f64::min(min.x, tester.x);
+                max.x = f64::max(max.x, tester.x);
+
+                min.y = f64::min(min.y, tester.y);
+                max.y = f64::max(max.y, tester.y);
+
+                min.z = f64::min(min.z, tester.z);
+                max.z = f64::max(max.z, tester.z);
+            }
+        }
+    }
+    AABB::new(min, max)
+}
+
+//any affine placement of an object in one wrapper, build the matrix with
+//Matrix4::translate(..) * Matrix4::rotate(..) * Matrix4::scale(..), applied right to left
+#[derive(Clone)]
+pub struct Transform {
+    pub ptr: Arc<dyn Hittable>,
+    pub matrix: Matrix4, //object to world, for a moving transform the pose mid shutter
+    pub inverse: Matrix4, //world to object
+    pub normal_matrix: Matrix4,
+    pub det: f64,
+    pub has_box: bool,
+    pub bbox: AABB,
+    pub motion: Option<Motion>,
+}
+
+impl Transform {
+    pub fn new(p: Arc<dyn Hittable>, matrix: Matrix4) -> Self {
+        let inverse = matrix
+            .inverse()
+            .expect("Transform needs an invertible matrix");
+        let mut bbox = AABB::default();
+        let hasbox = p.bounding_box(0.0, 1.0, &mut bbox);
+        Self {
+            ptr: (p),
+            matrix: (matrix),
+            inverse: (inverse),
+            normal_matrix: (inverse.transpose()),
+            det: (matrix.determinant3().abs()),
+            has_box: (hasbox),
+            bbox: (transformed_box(&bbox, &matrix)),
+            motion: None,
+        }
+    }
+
+    //translation and scale are interpolated linearly and the rotation is slerped by ray time
+    pub fn new_motion(p: Arc<dyn Hittable>, motion: Motion) -> Self {
+        let mut bbox = AABB::default();
+        let hasbox = p.bounding_box(motion.time0, motion.time1, &mut bbox);
+        let mut output = transformed_box(&bbox, &motion.start.matrix());
+        for i in 1..=MOTION_STEPS {
+            let pose = Pose::lerp(&motion.start, &motion.end, i as f64 / MOTION_STEPS as f64);
+            output = AABB::surrounding_box(output, transformed_box(&bbox, &pose.matrix()));
+        }
+        //corners swing on arcs between the samples, pad by the largest sag of such an arc
+        let mut angle = (motion.end.rotation * motion.start.rotation.conjugate()).angle();
+        if angle > PI {
+            angle = 2.0 * PI - angle;
+        }
+        let far = Vec3::new(
+            bbox.minimum.x.abs().max(bbox.maximum.x.abs()),
+            bbox.minimum.y.abs().max(bbox.maximum.y.abs()),
+            bbox.minimum.z.abs().max(bbox.maximum.z.abs()),
+        );
+        let reach = (far * motion.start.scale)
+            .length()
+            .max((far * motion.end.scale).length());
+        let sag = reach * (1.0 - (angle / MOTION_STEPS as f64 / 2.0).cos());
+        let pad = Vec3::new(sag, sag, sag);
+        let middle = motion.pose((motion.time0 + motion.time1) / 2.0);
+        let matrix = middle.matrix();
+        let inverse = middle.inverse();
+        Self {
+            ptr: (p),
+            matrix: (matrix),
+            inverse: (inverse),
+            normal_matrix: (inverse.transpose()),
+            det: (matrix.determinant3().abs()),
+            has_box: (hasbox),
+            bbox: (AABB::new(output.minimum - pad, output.maximum + pad)),
+            motion: Some(motion),
+        }
+    }
+
+    //object to world and world to object at a ray time
+    pub fn matrices(&self, time: f64) -> (Matrix4, Matrix4) {
+        match &self.motion {
+            Some(motion) => {
+                let pose = motion.pose(time);
+                (pose.matrix(), pose.inverse())
+            }
+            None => (self.matrix, self.inverse),
+        }
+    }
+
+    //the direction is not normalized, so t is the same in both spaces
+    pub fn to_object(&self, r: &Ray) -> Ray {
+        let (_, inverse) = self.matrices(r.time);
+        Ray {
+            point: inverse.transform_point(&r.point),
+            direct: inverse.transform_vector(&r.direct),
+            time: r.time,
+            wavelength: r.wavelength,
+        }
+    }
+
+    //moves a hit found with to_object(r) back into world space
+    pub fn to_world(&self, r: &Ray, rec: &mut HitRecord) {
+        let (matrix, inverse) = self.matrices(r.time);
+        let normal_matrix = match self.motion {
+            Some(_) => inverse.transpose(),
+            None => self.normal_matrix,
+        };
+        let outward_normal = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let outward_normal = Vec3::unit_vector(normal_matrix.transform_vector(&outward_normal));
+        rec.p = matrix.transform_point(&rec.p);
+        rec.set_face_normal(r, &outward_normal);
+        rec.tangent = matrix.transform_vector(&rec.tangent);
+        rec.bitangent = matrix.transform_vector(&rec.bitangent);
+    }
+
+    pub fn translate(p: Arc<dyn Hittable>, offset: Vec3) -> Self {
+        Self::new(p, Matrix4::translate(offset))
+    }
+
+    pub fn rotate(p: Arc<dyn Hittable>, axis: Vec3, angle: f64) -> Self {
+        Self::new(p, Matrix4::rotate(axis, angle))
+    }
+
+    pub fn scale(p: Arc<dyn Hittable>, factor: Vec3) -> Self {
+        Self::new(p, Matrix4::scale(factor))
+    }
+
+    pub fn look_at(p: Arc<dyn Hittable>, from: Point, at: Point, vup: Vec3) -> Self {
+        Self::new(p, Matrix4::look_at(from, at, vup))
+    }
+}
+
+impl Hittable for Transform {
+    fn bounding_box(&self, _time0: f64, _time1: f64, output_box: &mut AABB) -> bool {
+        *output_box = self.bbox;
+        self.has_box
+    }
+
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let object_r = self.to_object(r);
+        if !self.ptr.hit(&object_r, t_min, t_max, rec) {
+            return false;
+        }
+        self.to_world(r, rec);
+        true
+    }
+
+    //pdf_value and random carry no time, a moving transform uses its pose mid shutter like MovingSphere
+    //the solid angle pdf picks up the jacobian of the direction change, |det| / |A u|^3
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let object_v = Vec3::unit_vector(self.inverse.transform_vector(v));
+        let pdf = self
+            .ptr
+            .pdf_value(&self.inverse.transform_point(o), &object_v);
+        let stretch = self.matrix.transform_vector(&object_v).length();
+        pdf * stretch * stretch * stretch / self.det
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.matrix
+            .transform_vector(&self.ptr.random(&self.inverse.transform_point(o)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::basic_tools::matrix::Quaternion;
+    use super::super::super::material::lambertian::Lambertian;
+    use super::super::sphere::Sphere;
+    use super::super::xy_rectangle::RotateX;
+    use super::*;
+
+    fn inside(outer: &AABB, inner: &AABB) -> bool {
+        outer.minimum.x <= inner.minimum.x
+            && outer.minimum.y <= inner.minimum.y
+            && outer.minimum.z <= inner.minimum.z
+            && outer.maximum.x >= inner.maximum.x
+            && outer.maximum.y >= inner.maximum.y
+            && outer.maximum.z >= inner.maximum.z
+    }
+
+    #[test]
+    fn moving_bbox_covers_the_shutter() {
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Point::new(2.0, 0.0, 0.0),
+            1.0,
+            Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+        ));
+        let start = Pose::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Quaternion::default(),
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let end = Pose::new(
+            Vec3::new(5.0, 3.0, -2.0),
+            Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 170.0),
+            Vec3::new(2.0, 2.0, 2.0),
+        );
+        let motion = Motion::new(start, end, 0.0, 1.0);
+        let moving = Transform::new_motion(sphere.clone(), motion);
+        let mut bbox = AABB::default();
+        assert!(moving.bounding_box(0.0, 1.0, &mut bbox));
+        for time in [0.0, 0.5, 1.0] {
+            let still = Transform::new(sphere.clone(), motion.pose(time).matrix());
+            let mut object = AABB::default();
+            still.bounding_box(0.0, 1.0, &mut object);
+            assert!(inside(&bbox, &object), "not covered at time {}", time);
+        }
+    }
+
+    #[test]
+    fn rotate_x_matches_rotatex() {
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Point::new(0.0, 2.0, 1.0),
+            0.5,
+            Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+        ));
+        let matrix = Transform::new(sphere.clone(), Matrix4::rotate_x(20.0));
+        let rotate = RotateX::new(sphere, 20.0);
+        let origin = Point::new(0.0, 0.0, -10.0);
+        let mut hits = 0;
+        for i in 0..21 {
+            for j in 0..21 {
+                let target = Point::new(0.0, -1.0 + i as f64 * 0.2, -1.0 + j as f64 * 0.2);
+                let r = Ray::new(origin, target - origin, 0.0);
+                let mut a = HitRecord::default();
+                let mut b = HitRecord::default();
+                let hit_a = matrix.hit(&r, 0.001, f64::MAX, &mut a);
+                let hit_b = rotate.hit(&r, 0.001, f64::MAX, &mut b);
+                assert_eq!(hit_a, hit_b);
+                if hit_a {
+                    hits += 1;
+                    assert!((a.t - b.t).abs() < 1e-9);
+                    assert!((a.normal - b.normal).length() < 1e-9);
+                    assert_eq!(a.front_face, b.front_face);
+                }
+            }
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn euler_matches_matrices() {
+        let angles = Vec3::new(20.0, -35.0, 50.0);
+        let q = Quaternion::from_euler(angles).matrix();
+        let m =
+            Matrix4::rotate_z(angles.z) * Matrix4::rotate_y(angles.y) * Matrix4::rotate_x(angles.x);
+        for (row_q, row_m) in q.m.iter().zip(m.m.iter()) {
+            for (a, b) in row_q.iter().zip(row_m.iter()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -3,13 +3,50 @@ use crate::material::lambertian::Lambertian;
 use super::super::basic_tools;
 use super::super::material::metal::Material;
 use super::aabb::AABB;
-use super::hittable_list::HittableList;
-use super::hittable_origin::{HitRecord, Hittable};
+use super::bvh::BVHNode;
+use super::hittable_list::{area_pdf_value, HittableList};
+use super::hittable_origin::{random_double, HitRecord, Hittable};
+use super::pdf::Distribution1D;
 use crate::texture::text::ObjectTexture;
 use basic_tools::{ray::Ray, vec3::Point, vec3::Vec3};
 use image::GenericImageView;
+use std::collections::HashMap;
+use std::f64::INFINITY;
 use std::path::Path;
 use std::sync::Arc;
+//texture coordinates and per-vertex tangents of a triangle from a mesh with uvs
+#[derive(Clone, Copy, Default)]
+pub struct TriangleUV {
+    pub uv: [[f64; 2]; 3],
+    pub tangents: [Vec3; 3],
+    pub bitangents: [Vec3; 3],
+}
+
+impl TriangleUV {
+    //the triangle's own dp/du and dp/dv at all three corners, for a lone triangle
+    pub fn flat(p: [Point; 3], uv: [[f64; 2]; 3]) -> Self {
+        let (dpdu, dpdv) = triangle_tangents(p, uv);
+        Self {
+            uv: (uv),
+            tangents: ([dpdu; 3]),
+            bitangents: ([dpdv; 3]),
+        }
+    }
+}
+
+//dp/du and dp/dv of the flat triangle, zero when the uvs are degenerate
+pub fn triangle_tangents(p: [Point; 3], uv: [[f64; 2]; 3]) -> (Vec3, Vec3) {
+    let e1 = p[1] - p[0];
+    let e2 = p[2] - p[0];
+    let (du1, dv1) = (uv[1][0] - uv[0][0], uv[1][1] - uv[0][1]);
+    let (du2, dv2) = (uv[2][0] - uv[0][0], uv[2][1] - uv[0][1]);
+    let det = du1 * dv2 - du2 * dv1;
+    if det.abs() < 1e-12 {
+        return (Vec3::default(), Vec3::default());
+    }
+    ((e1 * dv2 - e2 * dv1) / det, (e2 * du1 - e1 * du2) / det)
+}
+
 pub struct Triangle<M>
 where
     M: Material,
@@ -24,6 +61,7 @@ where
     pub mp: M,
     pub minimum: Point,
     pub maximum: Point,
+    pub uv: Option<TriangleUV>, //without it u and v are the barycentric coordinates
 }
 
 fn min_three(x: f64, y: f64, z: f64) -> f64 {
@@ -54,16 +92,29 @@ impl<M: Material> Triangle<M> {
 
         Self {
             a: (a),
-            b: (b),            c: (c),
+            b: (b),
+            c: (c),
             a1: fa,
             b1: fb,
             c1: fc,
             d1: fd,
             mp: mat,
-            minimum: Point::new(xmin-0.0001, ymin-0.0001, zmin-0.0001),
-            maximum: Point::new(xmax+0.0001, ymax+0.0001, zmax+0.0001),
+            minimum: Point::new(xmin - 0.0001, ymin - 0.0001, zmin - 0.0001),
+            maximum: Point::new(xmax + 0.0001, ymax + 0.0001, zmax + 0.0001),
+            uv: None,
+        }
+    }
+
+    pub fn new_uv(a: Point, b: Point, c: Point, uv: TriangleUV, mat: M) -> Self {
+        Self {
+            uv: Some(uv),
+            ..Self::new(a, b, c, mat)
         }
     }
+
+    pub fn area(&self) -> f64 {
+        Vec3::cross(self.b - self.a, self.c - self.a).length() / 2.0
+    }
 }
 
 impl<M: Material> Hittable for Triangle<M> {
@@ -94,29 +145,95 @@ impl<M: Material> Hittable for Triangle<M> {
 
         let outward_normal = Vec3::unit_vector(Vec3::cross(self.a - self.b, self.a - self.c));
         rec.set_face_normal(r, &outward_normal);
-        let a1 = self.a.x - self.b.x;
-        let b1 = self.a.x - self.c.x;
-        let c1 = self.a.x - p.x;
-        let a2 = self.a.y - self.b.y;
-        let b2 = self.a.y - self.c.y;
-        let c2 = self.a.y - p.y;
-        rec.u = (c1 * b2 - b1 * c2) / (a1 * b2 - b1 * a2); //β
-        rec.v = (a1 * c2 - a2 * c1) / (a1 * b2 - b1 * a2); //γ
+        //areas of the sub triangles over the whole one, measured along the normal so no
+        //projection plane is degenerate
+        let n = Vec3::cross(self.b - self.a, self.c - self.a);
+        let n2 = Vec3::dot(&n, &n);
+        rec.u = Vec3::dot(&Vec3::cross(p - self.a, self.c - self.a), &n) / n2; //β
+        rec.v = Vec3::dot(&Vec3::cross(self.b - self.a, p - self.a), &n) / n2; //γ
         rec.t = t;
         rec.p = r.at(t);
         rec.mat_ptr = Some(&self.mp);
+        match &self.uv {
+            Some(uv) => {
+                let w = [1.0 - rec.u - rec.v, rec.u, rec.v];
+                let mut tex = [0.0; 2];
+                let mut tangent = Vec3::default();
+                let mut bitangent = Vec3::default();
+                for (i, wi) in w.iter().enumerate() {
+                    tex[0] += uv.uv[i][0] * wi;
+                    tex[1] += uv.uv[i][1] * wi;
+                    tangent += uv.tangents[i] * *wi;
+                    bitangent += uv.bitangents[i] * *wi;
+                }
+                rec.u = tex[0];
+                rec.v = tex[1];
+                //gram-schmidt against the normal keeps both in the plane of the triangle
+                rec.tangent = tangent - outward_normal * Vec3::dot(&tangent, &outward_normal);
+                rec.bitangent = bitangent - outward_normal * Vec3::dot(&bitangent, &outward_normal);
+            }
+            None => {
+                rec.tangent = Vec3::default();
+                rec.bitangent = Vec3::default();
+            }
+        }
         true
     }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let mut rec = HitRecord::default();
+        if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
+            return 0.0;
+        }
+
+        let distance_squared = rec.t * rec.t * v.length_squared();
+        let cosine = (Vec3::dot(v, &rec.normal) / v.length()).abs();
+
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        //uniform on the triangle
+        let r1 = random_double().sqrt();
+        let r2 = random_double();
+        let random_point = self.a * (1.0 - r1) + self.b * (r1 * (1.0 - r2)) + self.c * (r1 * r2);
+        random_point - *o
+    }
 }
 
 pub struct Object {
     pub surface: HittableList,
+    pub areas: Distribution1D, //triangles are picked proportionally to their area
+    pub bvh: Option<BVHNode>,  //over the triangles of a mesh, used for hits and light pdfs
 }
 
 impl Object {
+    //the areas of arbitrary hittables are unknown, so they are picked uniformly
     pub fn new_hittable(surfaces: &HittableList) -> Self {
         Self {
             surface: (surfaces.clone()),
+            areas: (Distribution1D::new(vec![1.0; surfaces.objects.len()])),
+            bvh: None,
+        }
+    }
+
+    //a mesh, its triangles picked by area
+    fn new_mesh(surface: HittableList, areas: Vec<f64>) -> Self {
+        let bvh = if surface.objects.is_empty() {
+            None
+        } else {
+            Some(BVHNode::new(
+                surface.objects.clone(),
+                0,
+                surface.objects.len(),
+                0.0,
+                1.0,
+            ))
+        };
+        Self {
+            surface: (surface),
+            areas: (Distribution1D::new(areas)),
+            bvh: (bvh),
         }
     }
 
@@ -138,6 +255,7 @@ impl Object {
         let (models, _materials) = cornell_box.expect("Failed to load OBJ file");
 
         let mut new_object = HittableList::default();
+        let mut areas = Vec::new();
         for (_i, m) in models.iter().enumerate() {
             let mesh = &m.mesh;
             // println!("total surface: {}", mesh.indices.len() / 4);
@@ -155,19 +273,19 @@ impl Object {
                 let p2 = points[mesh.indices[v * 4 + 1] as usize];
                 let p3 = points[mesh.indices[v * 4 + 2] as usize];
                 let trian = Triangle::new(p1, p2, p3, mat.clone());
+                areas.push(trian.area());
                 new_object.add(Arc::new(trian));
 
                 let p1 = points[mesh.indices[v * 4] as usize];
                 let p2 = points[mesh.indices[v * 4 + 2] as usize];
                 let p3 = points[mesh.indices[v * 4 + 3] as usize];
                 let trian = Triangle::new(p1, p2, p3, mat.clone());
+                areas.push(trian.area());
                 new_object.add(Arc::new(trian));
             }
             points.clear();
         }
-        Self {
-            surface: (new_object),
-        }
+        Self::new_mesh(new_object, areas)
     }
 
     pub fn new<M>(filename: &String, mat: M, scale: f64) -> Self
@@ -189,6 +307,7 @@ impl Object {
         println!("# of materials: {}", materials.len()); */
 
         let mut new_object = HittableList::default();
+        let mut areas = Vec::new();
         for (_i, m) in models.iter().enumerate() {
             let mesh = &m.mesh;
             //  println!("total surface: {}", mesh.indices.len() / 3);
@@ -200,18 +319,73 @@ impl Object {
                 let p = Point::new(x, y, z);
                 points.push(p);
             }
+            let has_uv =
+                !mesh.texcoords.is_empty() && mesh.texcoord_indices.len() == mesh.indices.len();
+            let face_uv = |f: usize| {
+                let mut uv = [[0.0; 2]; 3];
+                for (j, t) in uv.iter_mut().enumerate() {
+                    let k = mesh.texcoord_indices[f * 3 + j] as usize;
+                    *t = [
+                        mesh.texcoords[2 * k] as f64,
+                        mesh.texcoords[2 * k + 1] as f64,
+                    ];
+                }
+                uv
+            };
+            //per-vertex tangents average the tangents of the faces around the vertex, so the
+            //shading is smooth across edges and the length stays that of dp/du; a vertex is
+            //keyed by its position and texture coordinate, which splits it at uv seams
+            let corner = |f: usize, j: usize| {
+                (
+                    mesh.indices[f * 3 + j] as usize,
+                    mesh.texcoord_indices[f * 3 + j] as usize,
+                )
+            };
+            let mut vertex_tangents: HashMap<(usize, usize), (Vec3, Vec3, f64)> = HashMap::new();
+            if has_uv {
+                for f in 0..mesh.indices.len() / 3 {
+                    let p = [0, 1, 2].map(|j| points[mesh.indices[f * 3 + j] as usize]);
+                    let (dpdu, dpdv) = triangle_tangents(p, face_uv(f));
+                    if dpdu.near_zero() && dpdv.near_zero() {
+                        continue;
+                    }
+                    for j in 0..3 {
+                        let sum = vertex_tangents.entry(corner(f, j)).or_insert((
+                            Vec3::default(),
+                            Vec3::default(),
+                            0.0,
+                        ));
+                        sum.0 += dpdu;
+                        sum.1 += dpdv;
+                        sum.2 += 1.0;
+                    }
+                }
+            }
+            let averaged = |f: usize, j: usize| match vertex_tangents.get(&corner(f, j)) {
+                Some((dpdu, dpdv, count)) => (*dpdu / *count, *dpdv / *count),
+                None => (Vec3::default(), Vec3::default()),
+            };
             for v in 0..mesh.indices.len() / 3 {
                 let p1 = points[mesh.indices[v * 3] as usize];
                 let p2 = points[mesh.indices[v * 3 + 1] as usize];
                 let p3 = points[mesh.indices[v * 3 + 2] as usize];
-                let trian = Triangle::new(p1, p2, p3, mat.clone());
+                let trian = if has_uv {
+                    let corners = [0, 1, 2].map(|j| averaged(v, j));
+                    let uv = TriangleUV {
+                        uv: face_uv(v),
+                        tangents: corners.map(|c| c.0),
+                        bitangents: corners.map(|c| c.1),
+                    };
+                    Triangle::new_uv(p1, p2, p3, uv, mat.clone())
+                } else {
+                    Triangle::new(p1, p2, p3, mat.clone())
+                };
+                areas.push(trian.area());
                 new_object.add(Arc::new(trian));
             }
             points.clear();
         }
-        Self {
-            surface: (new_object),
-        }
+        Self::new_mesh(new_object, areas)
     }
 
     #[allow(clippy::needless_range_loop)]
@@ -229,6 +403,7 @@ impl Object {
         let (models, _materials) = cornell_box.expect("Failed to load OBJ file");
 
         let mut new_object = HittableList::default();
+        let mut areas = Vec::new();
         for (i, m) in models.iter().enumerate() {
             let mesh = &m.mesh;
             println!("total surface: {}", mesh.indices.len() / 3);
@@ -262,14 +437,13 @@ impl Object {
                 let p3 = points[mesh.indices[v * 3 + 2] as usize];
                 let mat = Lambertian::newp(ObjectTexture::new(coor_tmp[v], image_arc.clone()));
                 let trian = Triangle::new(p1, p2, p3, mat);
+                areas.push(trian.area());
                 new_object.add(Arc::new(trian));
             }
             points.clear();
         }
 
-        Self {
-            surface: (new_object),
-        }
+        Self::new_mesh(new_object, areas)
     }
 }
 
@@ -280,6 +454,52 @@ impl Hittable for Object {
     }
 
     fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
-        self.surface.hit(r, t_min, t_max, rec)
+        match &self.bvh {
+            Some(bvh) => bvh.hit(r, t_min, t_max, rec),
+            None => self.surface.hit(r, t_min, t_max, rec),
+        }
+    }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        match &self.bvh {
+            Some(bvh) => {
+                let total_area = self.areas.func_int * self.areas.count() as f64;
+                area_pdf_value(bvh, total_area, o, v)
+            }
+            None => self.surface.weighted_pdf_value(&self.areas, o, v),
+        }
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.surface.weighted_random(&self.areas, o)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uv_on_a_face_parallel_to_z() {
+        let triangle = Triangle::new_uv(
+            Point::new(2.0, 0.0, 0.0),
+            Point::new(2.0, 1.0, 0.0),
+            Point::new(2.0, 0.0, 1.0),
+            TriangleUV::flat(
+                [
+                    Point::new(2.0, 0.0, 0.0),
+                    Point::new(2.0, 1.0, 0.0),
+                    Point::new(2.0, 0.0, 1.0),
+                ],
+                [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            ),
+            Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+        );
+        let r = Ray::new(Point::new(0.0, 0.25, 0.5), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::default();
+        assert!(triangle.hit(&r, 0.001, f64::MAX, &mut rec));
+        assert!(rec.u.is_finite() && rec.v.is_finite());
+        assert!((rec.u - 0.25).abs() < 1e-9);
+        assert!((rec.v - 0.5).abs() < 1e-9);
     }
 }
@@ -3,8 +3,9 @@ use crate::basic_tools::camera::degrees_to_radians;
 use super::super::basic_tools;
 use super::super::material::metal::Material;
 use super::aabb::AABB;
-use super::hittable_list::HittableList;
+use super::hittable_list::{area_pdf_value, HittableList};
 use super::hittable_origin::{random_t, HitRecord, Hittable};
+use super::pdf::Distribution1D;
 use basic_tools::{ray::Ray, vec3::Point, vec3::Vec3};
 use std::f64::INFINITY;
 use std::sync::Arc;
@@ -56,6 +57,8 @@ impl<M: Material> Hittable for XYRectangle<M> {
         }
         rec.u = (x - self.x0) / (self.x1 - self.x0);
         rec.v = (y - self.y0) / (self.y1 - self.y0);
+        rec.tangent = Vec3::new(self.x1 - self.x0, 0.0, 0.0);
+        rec.bitangent = Vec3::new(0.0, self.y1 - self.y0, 0.0);
         rec.t = t;
         let outward_normal = Vec3::new(0.0, 0.0, 1.0);
         rec.set_face_normal(r, &outward_normal);
@@ -67,7 +70,7 @@ impl<M: Material> Hittable for XYRectangle<M> {
     fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
         let mut rec = HitRecord::default();
         if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
-            return 1.0;
+            return 0.0;
         }
 
         let area = (self.x1 - self.x0) * (self.y1 - self.y0);
@@ -133,6 +136,8 @@ impl<M: Material> Hittable for XZRectangle<M> {
         }
         rec.u = (x - self.x0) / (self.x1 - self.x0);
         rec.v = (z - self.z0) / (self.z1 - self.z0);
+        rec.tangent = Vec3::new(self.x1 - self.x0, 0.0, 0.0);
+        rec.bitangent = Vec3::new(0.0, 0.0, self.z1 - self.z0);
         rec.t = t;
         let outward_normal = Vec3::new(0.0, 1.0, 0.0);
         rec.set_face_normal(r, &outward_normal);
@@ -144,7 +149,7 @@ impl<M: Material> Hittable for XZRectangle<M> {
     fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
         let mut rec = HitRecord::default();
         if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
-            return 1.0;
+            return 0.0;
         }
 
         let area = (self.x1 - self.x0) * (self.z1 - self.z0);
@@ -209,6 +214,8 @@ impl<M: Material> Hittable for YZRectangle<M> {
         }
         rec.u = (y - self.y0) / (self.y1 - self.y0);
         rec.v = (z - self.z0) / (self.z1 - self.z0);
+        rec.tangent = Vec3::new(0.0, self.y1 - self.y0, 0.0);
+        rec.bitangent = Vec3::new(0.0, 0.0, self.z1 - self.z0);
         rec.t = t;
         let outward_normal = Vec3::new(1.0, 0.0, 0.0);
         rec.set_face_normal(r, &outward_normal);
@@ -219,7 +226,7 @@ impl<M: Material> Hittable for YZRectangle<M> {
     fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
         let mut rec = HitRecord::default();
         if !self.hit(&Ray::new(*o, *v, 0.0), 0.001, INFINITY, &mut rec) {
-            return 1.0;
+            return 0.0;
         }
 
         let area = (self.y1 - self.y0) * (self.z1 - self.z0);
@@ -244,6 +251,7 @@ pub struct Cube {
     pub box_min: Point,
     pub box_max: Point,
     pub slides: HittableList,
+    pub areas: Distribution1D,
 }
 
 impl Cube {
@@ -295,10 +303,15 @@ impl Cube {
         slide.add(Arc::new(YZRectangle::new(
             p0.y, p1.y, p0.z, p1.z, p0.x, ptr,
         )));
+        let dx = p1.x - p0.x;
+        let dy = p1.y - p0.y;
+        let dz = p1.z - p0.z;
+        let areas = vec![dx * dy, dx * dy, dx * dz, dx * dz, dy * dz, dy * dz];
         Self {
             box_min: (p0),
             box_max: (p1),
             slides: (slide),
+            areas: (Distribution1D::new(areas)),
         }
     }
 }
@@ -312,6 +325,15 @@ impl Hittable for Cube {
     fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
         self.slides.hit(r, t_min, t_max, rec)
     }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        let total_area = self.areas.func_int * self.areas.count() as f64;
+        area_pdf_value(&self.slides, total_area, o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.slides.weighted_random(&self.areas, o)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -356,6 +378,14 @@ impl Hittable for Translate {
         rec.set_face_normal(&moved_r, &rec.normal.clone());
         true
     }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr.as_ref().unwrap().pdf_value(&(*o - self.offset), v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.ptr.as_ref().unwrap().random(&(*o - self.offset))
+    }
 }
 
 #[derive(Clone, Default)]
@@ -409,6 +439,22 @@ impl RotateY {
             bbox: (AABB::new(min, max)),
         }
     }
+
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x - self.sin_theta * v.z,
+            v.y,
+            self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x + self.sin_theta * v.z,
+            v.y,
+            -self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
 }
 
 impl Hittable for RotateY {
@@ -438,19 +484,37 @@ impl Hittable for RotateY {
         }
 
         let mut p = rec.p;
-        let mut normal = rec.normal;
+        //the child turned its normal toward the ray, rotate the outward one and compare in world space
+        let outward = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let mut normal = outward;
 
         p.x = self.cos_theta * rec.p.x + self.sin_theta * rec.p.z;
         p.z = -self.sin_theta * rec.p.x + self.cos_theta * rec.p.z;
 
-        normal.x = self.cos_theta * rec.normal.x + self.sin_theta * rec.normal.z;
-        normal.z = -self.sin_theta * rec.normal.x + self.cos_theta * rec.normal.z;
+        normal.x = self.cos_theta * outward.x + self.sin_theta * outward.z;
+        normal.z = -self.sin_theta * outward.x + self.cos_theta * outward.z;
 
         rec.p = p;
-        rec.set_face_normal(&rotated_r, &normal);
+        rec.set_face_normal(r, &normal);
+        rec.tangent = self.to_world(&rec.tangent);
+        rec.bitangent = self.to_world(&rec.bitangent);
 
         true
     }
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr
+            .as_ref()
+            .unwrap()
+            .pdf_value(&self.to_object(o), &self.to_object(v))
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.to_world(&self.ptr.as_ref().unwrap().random(&self.to_object(o)))
+    }
 }
 
 #[derive(Clone, Default)]
@@ -502,6 +566,22 @@ impl RotateX {
             bbox: (AABB::new(min, max)),
         }
     }
+
+    fn to_object(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.x,
+            self.cos_theta * v.y - self.sin_theta * v.z,
+            self.sin_theta * v.y + self.cos_theta * v.z,
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            v.x,
+            self.cos_theta * v.y + self.sin_theta * v.z,
+            -self.sin_theta * v.y + self.cos_theta * v.z,
+        )
+    }
 }
 
 impl Hittable for RotateX {
@@ -531,19 +611,37 @@ impl Hittable for RotateX {
         }
 
         let mut p = rec.p;
-        let mut normal = rec.normal;
+        //the child turned its normal toward the ray, rotate the outward one and compare in world space
+        let outward = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let mut normal = outward;
 
         p.y = self.cos_theta * rec.p.y + self.sin_theta * rec.p.z;
         p.z = -self.sin_theta * rec.p.y + self.cos_theta * rec.p.z;
 
-        normal.y = self.cos_theta * rec.normal.y + self.sin_theta * rec.normal.z;
-        normal.z = -self.sin_theta * rec.normal.y + self.cos_theta * rec.normal.z;
+        normal.y = self.cos_theta * outward.y + self.sin_theta * outward.z;
+        normal.z = -self.sin_theta * outward.y + self.cos_theta * outward.z;
 
         rec.p = p;
-        rec.set_face_normal(&rotated_r, &normal);
+        rec.set_face_normal(r, &normal);
+        rec.tangent = self.to_world(&rec.tangent);
+        rec.bitangent = self.to_world(&rec.bitangent);
 
         true
     }
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr
+            .as_ref()
+            .unwrap()
+            .pdf_value(&self.to_object(o), &self.to_object(v))
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.to_world(&self.ptr.as_ref().unwrap().random(&self.to_object(o)))
+    }
 }
 pub struct FlipFace {
     pub ptr: Option<Arc<dyn Hittable>>,
@@ -569,4 +667,12 @@ impl Hittable for FlipFace {
         rec.front_face = !rec.front_face;
         true
     }
+
+    fn pdf_value(&self, o: &Point, v: &Vec3) -> f64 {
+        self.ptr.as_ref().unwrap().pdf_value(o, v)
+    }
+
+    fn random(&self, o: &Vec3) -> Vec3 {
+        self.ptr.as_ref().unwrap().random(o)
+    }
 }
@@ -0,0 +1,68 @@
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+
+//what a ray sees when it leaves the scene
+pub trait Background: Send + Sync {
+    fn value(&self, r: &Ray) -> Color;
+
+    //whether random/pdf_value are meaningful and should join the light sampling
+    fn importance_sampled(&self) -> bool {
+        false
+    }
+
+    fn pdf_value(&self, _v: &Vec3) -> f64 {
+        0.0
+    }
+
+    fn random(&self) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SolidBackground {
+    pub color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(col: Color) -> Self {
+        Self { color: (col) }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _r: &Ray) -> Color {
+        self.color
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct GradientBackground {
+    pub bottom: Color,
+    pub top: Color,
+}
+
+impl GradientBackground {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Self {
+            bottom: (bottom),
+            top: (top),
+        }
+    }
+
+    pub fn pink_blue() -> Self {
+        Self::new(
+            Color::new(1.0, 192.0 / 255.0, 203.0 / 255.0),
+            Color::new(0.5, 0.7, 1.0),
+        )
+    }
+}
+
+impl Background for GradientBackground {
+    fn value(&self, r: &Ray) -> Color {
+        let t = 0.5 * (Vec3::unit_vector(r.direct).y + 1.0);
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
@@ -0,0 +1,47 @@
+use super::super::basic_tools::{
+    camera::degrees_to_radians,
+    vec3::{Color, Point, Vec3},
+};
+use super::super::material::metal::ONB;
+use super::light_origin::{Light, LightSample};
+use std::f64::INFINITY;
+
+#[derive(Clone, Copy, Default)]
+pub struct DirectionalLight {
+    pub direction: Vec3, //towards the light
+    pub color: Color,
+    pub intensity: f64,
+    pub sin_half_angle: f64, //0 for a perfectly parallel light
+}
+
+impl DirectionalLight {
+    pub fn new(dir: Vec3, col: Color, intensity: f64) -> Self {
+        Self::new_disk(dir, col, intensity, 0.0)
+    }
+
+    //angular_diameter in degrees, e.g. about 0.53 for the sun
+    pub fn new_disk(dir: Vec3, col: Color, intensity: f64, angular_diameter: f64) -> Self {
+        Self {
+            direction: (Vec3::unit_vector(dir)),
+            color: (col),
+            intensity: (intensity),
+            sin_half_angle: (degrees_to_radians(angular_diameter / 2.0).sin()),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point, lrec: &mut LightSample) -> bool {
+        lrec.direction = if self.sin_half_angle > 0.0 {
+            let mut uvw = ONB::default();
+            uvw.build_from_w(self.direction);
+            //uniform in the cone, so radiance / pdf is the irradiance itself
+            Vec3::unit_vector(uvw.local_vec(Vec3::random_to_sphere(self.sin_half_angle, 1.0)))
+        } else {
+            self.direction
+        };
+        lrec.distance = INFINITY;
+        lrec.radiance = self.color * self.intensity;
+        true
+    }
+}
@@ -0,0 +1,173 @@
+use super::super::basic_tools::{
+    camera::degrees_to_radians,
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::{
+    hittable_origin::{clamp, random_double},
+    pdf::Distribution1D,
+};
+use super::background::Background;
+use image::{codecs::hdr::HdrDecoder, GenericImageView};
+use std::{f64::consts::PI, fs::File, io::BufReader, path::Path};
+
+//equirectangular (latitude-longitude) image surrounding the scene
+#[derive(Clone, Default)]
+pub struct EnvironmentMap {
+    pub data: Vec<Color>, //top row first, +y is the top of the image
+    pub width: usize,
+    pub height: usize,
+    pub intensity: f64,
+    pub sin_rotation: f64,
+    pub cos_rotation: f64,
+    pub marginal: Distribution1D,
+    pub conditional: Vec<Distribution1D>,
+}
+
+fn load_hdr(pathname: &str) -> (Vec<Color>, usize, usize) {
+    let file = File::open(Path::new(pathname)).unwrap();
+    let decoder = HdrDecoder::new(BufReader::new(file)).unwrap();
+    let meta = decoder.metadata();
+    let pixels = decoder.read_image_hdr().unwrap();
+    let data = pixels
+        .iter()
+        .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+        .collect();
+    (data, meta.width as usize, meta.height as usize)
+}
+
+fn load_ldr(pathname: &str) -> (Vec<Color>, usize, usize) {
+    let image = image::open(Path::new(pathname)).unwrap();
+    let width = image.width();
+    let height = image.height();
+    let color_scale = 1.0 / 255.0;
+    let mut data = Vec::new();
+    for j in 0..height {
+        for i in 0..width {
+            let pixel = image.get_pixel(i, j);
+            data.push(Color::new(
+                color_scale * pixel[0] as f64,
+                color_scale * pixel[1] as f64,
+                color_scale * pixel[2] as f64,
+            ));
+        }
+    }
+    (data, width as usize, height as usize)
+}
+
+impl EnvironmentMap {
+    //rotation is around the y axis in degrees
+    pub fn new(filename: &str, intensity: f64, rotation: f64) -> Self {
+        let pathname = String::from("img/") + filename;
+        let (data, width, height) = if pathname.to_lowercase().ends_with(".hdr") {
+            load_hdr(&pathname)
+        } else {
+            load_ldr(&pathname)
+        };
+        Self::new_data(data, width, height, intensity, rotation)
+    }
+
+    pub fn new_data(
+        data: Vec<Color>,
+        width: usize,
+        height: usize,
+        intensity: f64,
+        rotation: f64,
+    ) -> Self {
+        //rows near the poles cover less solid angle
+        let mut conditional = Vec::new();
+        for j in 0..height {
+            let sin_theta = (PI * (j as f64 + 0.5) / height as f64).sin();
+            let row = (0..width)
+                .map(|i| data[j * width + i].luminance() * sin_theta)
+                .collect();
+            conditional.push(Distribution1D::new(row));
+        }
+        let marginal = Distribution1D::new(conditional.iter().map(|c| c.func_int).collect());
+        let radius = degrees_to_radians(rotation);
+        Self {
+            data: (data),
+            width: (width),
+            height: (height),
+            intensity: (intensity),
+            sin_rotation: (radius.sin()),
+            cos_rotation: (radius.cos()),
+            marginal: (marginal),
+            conditional: (conditional),
+        }
+    }
+
+    fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_rotation * v.x - self.sin_rotation * v.z,
+            v.y,
+            self.sin_rotation * v.x + self.cos_rotation * v.z,
+        )
+    }
+
+    fn to_world(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_rotation * v.x + self.sin_rotation * v.z,
+            v.y,
+            -self.sin_rotation * v.x + self.cos_rotation * v.z,
+        )
+    }
+
+    //same longitude convention as get_sphere_uv, v = 0 at the top
+    fn direction_to_uv(&self, direction: &Vec3) -> (f64, f64) {
+        let d = Vec3::unit_vector(self.to_local(direction));
+        let theta = clamp(d.y, -1.0, 1.0).acos();
+        let phi = (-d.z).atan2(d.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    fn pixel(&self, u: f64, v: f64) -> (usize, usize) {
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        (i, j)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn value(&self, r: &Ray) -> Color {
+        if self.data.is_empty() {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let (u, v) = self.direction_to_uv(&r.direct);
+        let (i, j) = self.pixel(u, v);
+        self.data[j * self.width + i] * self.intensity
+    }
+
+    fn importance_sampled(&self) -> bool {
+        !self.data.is_empty() && self.marginal.func_int > 0.0
+    }
+
+    fn pdf_value(&self, direction: &Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let (i, j) = self.pixel(u, v);
+        let pdf_uv = self.conditional[j].func[i] / self.marginal.func_int;
+        pdf_uv / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn random(&self) -> Vec3 {
+        let mut pdf = 0.0;
+        let mut row = 0;
+        let mut col = 0;
+        let v = self
+            .marginal
+            .sample_continuous(random_double(), &mut pdf, &mut row);
+        let u = self.conditional[row].sample_continuous(random_double(), &mut pdf, &mut col);
+        let theta = v * PI;
+        let phi = u * 2.0 * PI;
+        let local = Vec3::new(
+            -phi.cos() * theta.sin(),
+            theta.cos(),
+            phi.sin() * theta.sin(),
+        );
+        self.to_world(&local)
+    }
+}
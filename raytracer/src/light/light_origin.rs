@@ -0,0 +1,13 @@
+use super::super::basic_tools::vec3::{Color, Point, Vec3};
+
+#[derive(Clone, Copy, Default)]
+pub struct LightSample {
+    pub direction: Vec3, //unit vector from the shading point towards the light
+    pub distance: f64,   //INFINITY for lights at infinity
+    pub radiance: Color, //incident radiance already divided by the sampling pdf
+}
+
+//lights that can not be hit by rays, only reached through shadow rays
+pub trait Light: Send + Sync {
+    fn sample(&self, p: &Point, lrec: &mut LightSample) -> bool;
+}
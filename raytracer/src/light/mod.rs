@@ -0,0 +1,8 @@
+pub mod background;
+pub mod directional_light;
+pub mod environment;
+pub mod light_list;
+pub mod light_origin;
+pub mod point_light;
+pub mod sky;
+pub mod spot_light;
@@ -0,0 +1,33 @@
+use super::super::basic_tools::vec3::{Color, Point};
+use super::light_origin::{Light, LightSample};
+
+#[derive(Clone, Copy, Default)]
+pub struct PointLight {
+    pub position: Point,
+    pub color: Color,
+    pub intensity: f64,
+}
+
+impl PointLight {
+    pub fn new(pos: Point, col: Color, intensity: f64) -> Self {
+        Self {
+            position: (pos),
+            color: (col),
+            intensity: (intensity),
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point, lrec: &mut LightSample) -> bool {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return false;
+        }
+        lrec.distance = distance_squared.sqrt();
+        lrec.direction = to_light / lrec.distance;
+        lrec.radiance = self.color * self.intensity / distance_squared;
+        true
+    }
+}
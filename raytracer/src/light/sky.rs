@@ -0,0 +1,142 @@
+use super::super::basic_tools::{
+    camera::degrees_to_radians,
+    ray::Ray,
+    spectrum::xyz_to_rgb,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::hittable_origin::clamp;
+use super::background::Background;
+use super::directional_light::DirectionalLight;
+use std::f64::consts::PI;
+
+//Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight"
+#[derive(Clone, Copy, Default)]
+pub struct PreethamSky {
+    pub sun_direction: Vec3,
+    pub turbidity: f64,
+    pub intensity: f64,
+    pub zenith: Vec3,         //(Y, x, y) at the zenith
+    pub perez: [[f64; 5]; 3], //A..E for Y, x and y
+    pub perez_sun: Vec3,      //F(0, theta_s) for Y, x and y
+}
+
+fn perez(coef: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1.0 + coef[0] * (coef[1] / cos_theta).exp())
+        * (1.0 + coef[2] * (coef[3] * gamma).exp() + coef[4] * gamma.cos().powi(2))
+}
+
+fn yxy_to_rgb(big_y: f64, x: f64, y: f64) -> Color {
+    if y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    xyz_to_rgb(&Vec3::new(x / y * big_y, big_y, (1.0 - x - y) / y * big_y))
+}
+
+//elevation above the horizon and azimuth from +z towards +x, both in degrees
+pub fn sun_direction(elevation: f64, azimuth: f64) -> Vec3 {
+    let el = degrees_to_radians(elevation);
+    let az = degrees_to_radians(azimuth);
+    Vec3::new(el.cos() * az.sin(), el.sin(), el.cos() * az.cos())
+}
+
+impl PreethamSky {
+    //turbidity from about 2 (clear) to 10 (hazy)
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64, intensity: f64) -> Self {
+        let t = turbidity;
+        let sun_direction = sun_direction(elevation, azimuth);
+        let theta_s = clamp(sun_direction.y, -1.0, 1.0).acos();
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (th, th2, th3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_yy = t * t * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let perez_coef = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let perez_sun = Vec3::new(
+            perez(&perez_coef[0], 1.0, theta_s),
+            perez(&perez_coef[1], 1.0, theta_s),
+            perez(&perez_coef[2], 1.0, theta_s),
+        );
+        Self {
+            sun_direction: (sun_direction),
+            turbidity: (turbidity),
+            intensity: (intensity),
+            zenith: (Vec3::new(zenith_y.max(0.0), zenith_x, zenith_yy)),
+            perez: (perez_coef),
+            perez_sun: (perez_sun),
+        }
+    }
+
+    pub fn sky_color(&self, direction: &Vec3) -> Color {
+        let d = Vec3::unit_vector(*direction);
+        //below the horizon keep the horizon color instead of extrapolating
+        let cos_theta = d.y.max(0.001);
+        let gamma = clamp(Vec3::dot(&d, &self.sun_direction), -1.0, 1.0).acos();
+        let big_y = self.zenith.x * perez(&self.perez[0], cos_theta, gamma) / self.perez_sun.x;
+        let x = self.zenith.y * perez(&self.perez[1], cos_theta, gamma) / self.perez_sun.y;
+        let y = self.zenith.z * perez(&self.perez[2], cos_theta, gamma) / self.perez_sun.z;
+        let col = yxy_to_rgb(big_y, x, y);
+        Color::new(col.x.max(0.0), col.y.max(0.0), col.z.max(0.0)) * self.intensity
+    }
+
+    //sunlight after Rayleigh and aerosol extinction along the sun's path through the air
+    pub fn sun_color(&self) -> Color {
+        let theta_s = clamp(self.sun_direction.y, -1.0, 1.0).acos();
+        if theta_s >= PI / 2.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let theta_deg = theta_s * 180.0 / PI;
+        let m = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_deg).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let alpha = 1.3;
+        let transmittance = |lambda: f64| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * m).exp();
+            let aerosol = (-beta * lambda.powf(-alpha) * m).exp();
+            rayleigh * aerosol
+        };
+        //wavelengths in micrometers for red, green and blue
+        Color::new(
+            transmittance(0.68),
+            transmittance(0.55),
+            transmittance(0.44),
+        )
+    }
+
+    //the matching sun, roughly 0.53 degrees wide
+    pub fn sun(&self, sun_intensity: f64) -> DirectionalLight {
+        DirectionalLight::new_disk(self.sun_direction, self.sun_color(), sun_intensity, 0.53)
+    }
+}
+
+impl Background for PreethamSky {
+    fn value(&self, r: &Ray) -> Color {
+        self.sky_color(&r.direct)
+    }
+}
@@ -0,0 +1,67 @@
+use super::super::basic_tools::{
+    camera::degrees_to_radians,
+    vec3::{Color, Point, Vec3},
+};
+use super::light_origin::{Light, LightSample};
+
+#[derive(Clone, Copy, Default)]
+pub struct SpotLight {
+    pub position: Point,
+    pub direction: Vec3,
+    pub color: Color,
+    pub intensity: f64,
+    pub cos_total_width: f64,
+    pub cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    //angles are half angles of the cone in degrees
+    pub fn new(
+        pos: Point,
+        target: Point,
+        col: Color,
+        intensity: f64,
+        total_width: f64,
+        falloff_start: f64,
+    ) -> Self {
+        let falloff_start = f64::min(falloff_start, total_width);
+        Self {
+            position: (pos),
+            direction: (Vec3::unit_vector(target - pos)),
+            color: (col),
+            intensity: (intensity),
+            cos_total_width: (degrees_to_radians(total_width).cos()),
+            cos_falloff_start: (degrees_to_radians(falloff_start).cos()),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta < self.cos_total_width {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        let delta =
+            (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        delta.powi(4)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point, lrec: &mut LightSample) -> bool {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared <= 0.0 {
+            return false;
+        }
+        lrec.distance = distance_squared.sqrt();
+        lrec.direction = to_light / lrec.distance;
+        let falloff = self.falloff(Vec3::dot(&-lrec.direction, &self.direction));
+        if falloff <= 0.0 {
+            return false;
+        }
+        lrec.radiance = self.color * self.intensity * falloff / distance_squared;
+        true
+    }
+}
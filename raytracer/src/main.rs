@@ -1,5 +1,6 @@
 pub mod basic_tools;
 pub mod hittable;
+pub mod light;
 pub mod material;
 pub mod texture;
 use basic_tools::render::render;
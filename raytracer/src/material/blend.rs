@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Point},
+};
+use super::super::hittable::{
+    hittable_origin::{clamp, random_double, HitRecord},
+    pdf::{WeightedPDF, PDF},
+};
+use super::metal::{Material, ScatterRecord};
+use crate::texture::text::{SolidColor, Texture};
+
+fn is_diffuse(srec: &ScatterRecord) -> bool {
+    !srec.is_specular && srec.pdf_ptr.is_some()
+}
+
+fn is_scattered(srec: &ScatterRecord) -> bool {
+    srec.is_specular || srec.pdf_ptr.is_some()
+}
+
+//picks one of the lobes that scattered in proportion to their weights, so a lobe that absorbs
+//(like a light) hands the path to the other one; a specular pick is followed with its attenuation
+//scaled by weight over pick chance, otherwise the ray is drawn from the non specular lobes
+pub fn blend_scatter(
+    materials: [&dyn Material; 2],
+    weight: f64,
+    r_in: &Ray,
+    rec: &HitRecord,
+    srec: &mut ScatterRecord,
+) -> bool {
+    let weights = [weight, 1.0 - weight];
+    let mut records = vec![ScatterRecord::default(), ScatterRecord::default()];
+    let mut scattered = [false; 2];
+    for i in 0..2 {
+        if weights[i] > 0.0 {
+            scattered[i] = materials[i].scatter(r_in, rec, &mut records[i]);
+        }
+        if !scattered[i] {
+            records[i] = ScatterRecord::default();
+        }
+    }
+    let scattered_weight: f64 = (0..2).filter(|i| scattered[*i]).map(|i| weights[i]).sum();
+    if scattered_weight <= 0.0 {
+        return false;
+    }
+    let lobe = if !scattered[1] || (scattered[0] && random_double() < weight) {
+        0
+    } else {
+        1
+    };
+    if records[lobe].is_specular {
+        *srec = records.swap_remove(lobe);
+        srec.attenuation *= scattered_weight;
+        return true;
+    }
+
+    let mut pdfs: Vec<Arc<dyn PDF>> = Vec::new();
+    let mut pdf_weights = Vec::new();
+    for i in 0..2 {
+        if is_diffuse(&records[i]) {
+            pdfs.push(records[i].pdf_ptr.as_ref().unwrap().clone());
+            pdf_weights.push(weights[i]);
+        }
+    }
+    srec.is_specular = false;
+    srec.attenuation = Color::new(1.0, 1.0, 1.0);
+    srec.pdf_ptr = Some(Arc::new(WeightedPDF::new(pdfs, pdf_weights)));
+    srec.lobe_records = records;
+    true
+}
+
+//the non specular lobes divided by the chance of having picked one of them, only lobes
+//that scattered took part in the pick
+pub fn blend_scattering_value(
+    materials: [&dyn Material; 2],
+    weight: f64,
+    r_in: &Ray,
+    rec: &HitRecord,
+    srec: &ScatterRecord,
+    scattered: &Ray,
+) -> Color {
+    let weights = [weight, 1.0 - weight];
+    let mut value = Color::new(0.0, 0.0, 0.0);
+    let mut total = 0.0;
+    let mut scattered_weight = 0.0;
+    for (i, lobe_rec) in srec.lobe_records.iter().enumerate() {
+        if is_diffuse(lobe_rec) {
+            value += materials[i].scattering_value(r_in, rec, lobe_rec, scattered) * weights[i];
+            total += weights[i];
+        }
+        if is_scattered(lobe_rec) {
+            scattered_weight += weights[i];
+        }
+    }
+    if total <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    value * scattered_weight / total
+}
+
+//two materials blended by a mask, the first channel of the mask is the weight of m1
+#[derive(Clone)]
+pub struct BlendMaterial<M, N, T>
+where
+    M: Material,
+    N: Material,
+    T: Texture,
+{
+    pub m1: M,
+    pub m2: N,
+    pub mask: T,
+}
+
+impl<M: Material, N: Material, T: Texture> BlendMaterial<M, N, T> {
+    pub fn new(mat1: M, mat2: N, mask: T) -> Self {
+        Self {
+            m1: (mat1),
+            m2: (mat2),
+            mask: (mask),
+        }
+    }
+
+    fn weight(&self, rec: &HitRecord) -> f64 {
+        clamp(self.mask.value(rec.u, rec.v, &rec.p).x, 0.0, 1.0)
+    }
+}
+
+impl<M: Material, N: Material> BlendMaterial<M, N, SolidColor> {
+    pub fn new_rate(mat1: M, mat2: N, rate: f64) -> Self {
+        Self::new(mat1, mat2, SolidColor::new(&Color::new(rate, rate, rate)))
+    }
+}
+
+impl<M: Material, N: Material, T: Texture> Material for BlendMaterial<M, N, T> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        blend_scatter([&self.m1, &self.m2], self.weight(rec), r_in, rec, srec)
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let w = self.weight(rec);
+        self.m1.scattering_pdf(r_in, rec, scattered) * w
+            + self.m2.scattering_pdf(r_in, rec, scattered) * (1.0 - w)
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        blend_scattering_value(
+            [&self.m1, &self.m2],
+            self.weight(rec),
+            r_in,
+            rec,
+            srec,
+            scattered,
+        )
+    }
+
+    fn emit(&self, u: f64, v: f64, p: &Point, r_in: &Ray, rec: &HitRecord) -> Color {
+        let w = self.weight(rec);
+        self.m1.emit(u, v, p, r_in, rec) * w + self.m2.emit(u, v, p, r_in, rec) * (1.0 - w)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.m1.is_dispersive() || self.m2.is_dispersive()
+    }
+}
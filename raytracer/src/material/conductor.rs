@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::{
+    hittable_origin::HitRecord,
+    pdf::{GGXPDF, PDF},
+};
+use super::metal::{Material, ScatterRecord, ONB};
+use super::microfacet::{distribution, fresnel_conductor, roughness_to_alpha, smith_g};
+
+//rough metal, GGX distribution with Smith masking and the complex Fresnel term
+#[derive(Clone)]
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub roughness: f64,
+    pub alpha: f64,
+}
+
+impl Conductor {
+    //eta and k for red, green and blue (roughly 650nm, 550nm, 450nm)
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Self {
+        Self {
+            eta: (eta),
+            k: (k),
+            roughness: (roughness),
+            alpha: (roughness_to_alpha(roughness)),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    pub fn aluminum(roughness: f64) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    fn is_smooth(&self) -> bool {
+        self.roughness < 0.01
+    }
+
+    fn local_directions(r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(rec.normal);
+        let wo = Vec3::unit_vector(uvw.to_local(-r_in.direct));
+        let wi = Vec3::unit_vector(uvw.to_local(scattered.direct));
+        (wo, wi)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        let unit_direction = Vec3::unit_vector(r_in.direct);
+        if self.is_smooth() {
+            let cos_theta = Vec3::dot(&-unit_direction, &rec.normal);
+            srec.specular_ray =
+                Ray::new(rec.p, Vec3::reflect(unit_direction, rec.normal), r_in.time);
+            srec.attenuation = fresnel_conductor(cos_theta, &self.eta, &self.k);
+            srec.is_specular = true;
+            srec.pdf_ptr = None;
+            return true;
+        }
+        srec.is_specular = false;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        srec.pdf_ptr = Some(Arc::new(GGXPDF::new(
+            rec.normal,
+            -unit_direction,
+            self.alpha,
+        )));
+        true
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let pdf = GGXPDF::new(rec.normal, -r_in.direct, self.alpha);
+        pdf.value(&scattered.direct)
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let (wo, wi) = Conductor::local_directions(r_in, rec, scattered);
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let wm = Vec3::unit_vector(wo + wi);
+        let fresnel = fresnel_conductor(Vec3::dot(&wi, &wm), &self.eta, &self.k);
+        //D G F / (4 cos_o cos_i), times cos_i
+        fresnel * (distribution(&wm, self.alpha) * smith_g(&wo, &wi, self.alpha) / (4.0 * wo.z))
+    }
+}
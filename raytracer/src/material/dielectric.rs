@@ -2,20 +2,139 @@ use super::super::basic_tools::{
     ray::Ray,
     vec3::{Color, Vec3},
 };
-use super::super::hittable::hittable_origin::random_double;
+use super::super::hittable::hittable_origin::{clamp, random_double, HitRecord};
 use super::metal::{Material, ScatterRecord};
+use super::thin_film::{Substrate, ThinFilm};
+
+//Fraunhofer d, F and C lines in micrometers
+const LAMBDA_D: f64 = 0.5876;
+const LAMBDA_F: f64 = 0.4861;
+const LAMBDA_C: f64 = 0.6563;
+
+//index of refraction as a function of wavelength, wavelengths in micrometers
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    None,
+    Cauchy(f64, f64),              //n = a + b / lambda^2
+    Sellmeier([f64; 3], [f64; 3]), //n^2 = 1 + sum b * lambda^2 / (lambda^2 - c)
+}
+
+impl Dispersion {
+    pub fn ior(&self, lambda: f64) -> f64 {
+        let l2 = lambda * lambda;
+        match self {
+            Dispersion::None => 1.0,
+            Dispersion::Cauchy(a, b) => a + b / l2,
+            Dispersion::Sellmeier(b, c) => {
+                let mut n2 = 1.0;
+                for i in 0..3 {
+                    n2 += b[i] * l2 / (l2 - c[i]);
+                }
+                n2.sqrt()
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Dielectric {
-    pub ir: f64, //index of refrection
+    pub ir: f64,           //index of refrection, at the d line when dispersive
+    pub absorption: Color, //Beer-Lambert coefficient per unit length inside the object
+    pub dispersion: Dispersion,
+    pub film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(index_of_ref: f64) -> Self {
-        Self { ir: (index_of_ref) }
+        Self::new_absorbing(index_of_ref, Color::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn new_absorbing(index_of_ref: f64, absorption: Color) -> Self {
+        Self {
+            ir: (index_of_ref),
+            absorption: (absorption),
+            dispersion: Dispersion::None,
+            film: None,
+        }
+    }
+
+    //e.g. a soap bubble is a film of ior 1.33 on a dielectric of ior 1.0
+    pub fn new_film(index_of_ref: f64, film: ThinFilm) -> Self {
+        Self {
+            film: Some(film),
+            ..Self::new(index_of_ref)
+        }
+    }
+
+    pub fn new_dispersion(dispersion: Dispersion) -> Self {
+        Self {
+            ir: (dispersion.ior(LAMBDA_D)),
+            absorption: (Color::new(0.0, 0.0, 0.0)),
+            dispersion: (dispersion),
+            film: None,
+        }
+    }
+
+    //Cauchy fit through the index at the d line and the Abbe number, lower abbe disperses more
+    pub fn new_abbe(index_of_ref: f64, abbe: f64) -> Self {
+        let b = (index_of_ref - 1.0)
+            / (abbe * (1.0 / (LAMBDA_F * LAMBDA_F) - 1.0 / (LAMBDA_C * LAMBDA_C)));
+        let a = index_of_ref - b / (LAMBDA_D * LAMBDA_D);
+        Self::new_dispersion(Dispersion::Cauchy(a, b))
+    }
+
+    //Schott N-BK7 crown glass
+    pub fn bk7() -> Self {
+        Self::new_dispersion(Dispersion::Sellmeier(
+            [1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653],
+        ))
+    }
+
+    //Schott SF11 dense flint glass
+    pub fn dense_flint() -> Self {
+        Self::new_dispersion(Dispersion::Sellmeier(
+            [1.73759695, 0.313747346, 1.89878101],
+            [0.013188707, 0.0623068142, 155.23629],
+        ))
+    }
+
+    //lambda in nm, 0 for rays that have not picked a wavelength
+    pub fn ior(&self, lambda: f64) -> f64 {
+        match self.dispersion {
+            Dispersion::None => self.ir,
+            _ if lambda <= 0.0 => self.ir,
+            _ => self.dispersion.ior(lambda / 1000.0),
+        }
+    }
+
+    //glass that lets through `color` after traveling `distance` inside it
+    pub fn new_tinted(index_of_ref: f64, color: Color, distance: f64) -> Self {
+        let coefficient = |c: f64| -c.max(1e-6).ln() / distance;
+        Self::new_absorbing(
+            index_of_ref,
+            Color::new(
+                coefficient(color.x),
+                coefficient(color.y),
+                coefficient(color.z),
+            ),
+        )
     }
 
-    fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
+    //a ray hitting the back face has traveled rec.t inside the object
+    pub fn transmittance(absorption: &Color, r_in: &Ray, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+        let distance = rec.t * r_in.direct.length();
+        Color::new(
+            (-absorption.x * distance).exp(),
+            (-absorption.y * distance).exp(),
+            (-absorption.z * distance).exp(),
+        )
+    }
+
+    pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
@@ -27,27 +146,41 @@ pub fn min(ls: f64, rs: f64) -> f64 {
     rs
 }
 impl Material for Dielectric {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &crate::hittable::hittable_origin::HitRecord,
-        srec: &mut ScatterRecord,
-    ) -> bool {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
         srec.is_specular = true;
         srec.pdf_ptr = None;
-        srec.attenuation = Color::new(1.0, 1.0, 1.0);
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
+        srec.attenuation = Dielectric::transmittance(&self.absorption, r_in, rec);
+        let ir = self.ior(r_in.wavelength);
+        let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
         let unit_direction = Vec3::unit_vector(r_in.direct);
         let cos_theta = min(Vec3::dot(&-unit_direction, &rec.normal), 1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = if cannot_refract
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double()
-        {
+        let reflect = if cannot_refract {
+            true
+        } else if let Some(film) = &self.film {
+            //a colored reflectance, pick by its mean and reweight
+            let (outside, inside) = if rec.front_face { (1.0, ir) } else { (ir, 1.0) };
+            let r = film.reflectance(
+                rec,
+                cos_theta,
+                outside,
+                &Substrate::Dielectric(inside),
+                r_in.wavelength,
+            );
+            let prob = clamp((r.x + r.y + r.z) / 3.0, 0.001, 0.999);
+            if random_double() < prob {
+                srec.attenuation = srec.attenuation * r / prob;
+                true
+            } else {
+                srec.attenuation =
+                    srec.attenuation * (Color::new(1.0, 1.0, 1.0) - r) / (1.0 - prob);
+                false
+            }
+        } else {
+            Dielectric::reflectance(cos_theta, refraction_ratio) > random_double()
+        };
+        let direction = if reflect {
             Vec3::reflect(unit_direction, rec.normal)
         } else {
             Vec3::refract(unit_direction, rec.normal, refraction_ratio)
@@ -55,4 +188,8 @@ impl Material for Dielectric {
         srec.specular_ray = Ray::new(rec.p, direction, r_in.time);
         true
     }
+
+    fn is_dispersive(&self) -> bool {
+        !matches!(self.dispersion, Dispersion::None)
+    }
 }
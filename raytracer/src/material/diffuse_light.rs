@@ -1,11 +1,44 @@
 use super::super::basic_tools::{
     ray::Ray,
-    vec3::{Color, Point},
+    spectrum::blackbody_color,
+    vec3::{Color, Point, Vec3},
 };
-use super::super::hittable::hittable_origin::HitRecord;
+use super::super::hittable::hittable_origin::{clamp, HitRecord};
 use crate::material::metal::{Material, ScatterRecord};
 use crate::texture::text::{SolidColor, Texture};
 
+//how the emitted radiance falls off away from the surface normal
+#[derive(Clone, Default)]
+pub enum EmissionProfile {
+    #[default]
+    Lambertian,
+    CosinePower(f64),
+    //relative radiance at evenly spaced angles from 0 (normal) to 90 degrees
+    Table(Vec<f64>),
+}
+
+impl EmissionProfile {
+    pub fn value(&self, cos_theta: f64) -> f64 {
+        match self {
+            EmissionProfile::Lambertian => 1.0,
+            EmissionProfile::CosinePower(n) => cos_theta.max(0.0).powf(*n),
+            EmissionProfile::Table(table) => {
+                if table.is_empty() {
+                    return 1.0;
+                }
+                if table.len() == 1 {
+                    return table[0];
+                }
+                let theta = clamp(cos_theta, 0.0, 1.0).acos();
+                let x = theta / (std::f64::consts::PI / 2.0) * (table.len() - 1) as f64;
+                let i = (x as usize).min(table.len() - 2);
+                let t = x - i as f64;
+                table[i] * (1.0 - t) + table[i + 1] * t
+            }
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct DiffuseLight<T>
 where
@@ -13,6 +46,8 @@ where
 {
     pub emit: T,
     pub light_intensity: f64,
+    pub two_sided: bool,
+    pub profile: EmissionProfile,
 }
 
 impl<T: Texture> DiffuseLight<T> {
@@ -20,27 +55,42 @@ impl<T: Texture> DiffuseLight<T> {
         Self {
             emit: a,
             light_intensity: intensity,
+            two_sided: false,
+            profile: EmissionProfile::Lambertian,
         }
     }
-}
-impl DiffuseLight<SolidColor> {
-    pub fn new_col(c: Color, intensity: f64) -> Self {
+
+    pub fn new_profile(a: T, intensity: f64, two_sided: bool, profile: EmissionProfile) -> Self {
         Self {
-            emit: SolidColor::new(&c),
+            emit: a,
             light_intensity: intensity,
+            two_sided: (two_sided),
+            profile: (profile),
         }
     }
 }
+impl DiffuseLight<SolidColor> {
+    pub fn new_col(c: Color, intensity: f64) -> Self {
+        Self::new(SolidColor::new(&c), intensity)
+    }
+
+    //color of a black body at kelvin degrees, e.g. 2700 for a warm bulb, 6500 for daylight
+    pub fn new_temperature(kelvin: f64, intensity: f64) -> Self {
+        Self::new(SolidColor::new(&blackbody_color(kelvin)), intensity)
+    }
+}
 
 impl<T: Texture> Material for DiffuseLight<T> {
     fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _srec: &mut ScatterRecord) -> bool {
         false
     }
 
-    fn emit(&self, u: f64, v: f64, p: &Point, _r_in: &Ray, rec: &HitRecord) -> Color {
-        if rec.front_face {
-            return self.emit.value(u, v, p) * self.light_intensity;
+    fn emit(&self, u: f64, v: f64, p: &Point, r_in: &Ray, rec: &HitRecord) -> Color {
+        if !rec.front_face && !self.two_sided {
+            return Color::new(0.0, 0.0, 0.0);
         }
-        Color::new(0.0, 0.0, 0.0)
+        //rec.normal already faces the incoming ray
+        let cos_theta = Vec3::dot(&-Vec3::unit_vector(r_in.direct), &rec.normal);
+        self.emit.value(u, v, p) * self.light_intensity * self.profile.value(cos_theta)
     }
 }
@@ -6,19 +6,37 @@ use super::super::basic_tools::{
     ray::Ray,
     vec3::{Color, Vec3},
 };
-use super::super::hittable::{hittable_origin::HitRecord, pdf::CosinePDF};
+use super::super::hittable::{
+    hittable_origin::{random_double, HitRecord},
+    pdf::CosinePDF,
+};
 use super::metal::{Material, ScatterRecord};
+use super::microfacet::fresnel_dielectric;
 
 #[derive(Clone)]
 pub struct Lambertian<T>
 where
     T: Texture,
 {
-    pub albedo: T, //反射率
+    pub albedo: T,              //反射率
+    pub clearcoat: Option<f64>, //index of refraction of a clear varnish on top
 }
 
 impl<T: Texture> Material for Lambertian<T> {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        if let Some(ior) = self.clearcoat {
+            //the coat reflects with probability equal to its fresnel reflectance
+            let unit_direction = Vec3::unit_vector(r_in.direct);
+            let cos_theta = Vec3::dot(&-unit_direction, &rec.normal);
+            if fresnel_dielectric(cos_theta, ior) > random_double() {
+                srec.is_specular = true;
+                srec.pdf_ptr = None;
+                srec.attenuation = Color::new(1.0, 1.0, 1.0);
+                srec.specular_ray =
+                    Ray::new(rec.p, Vec3::reflect(unit_direction, rec.normal), r_in.time);
+                return true;
+            }
+        }
         srec.is_specular = false;
         srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
         srec.pdf_ptr = Some(Arc::new(CosinePDF::new(rec.normal)));
@@ -30,17 +48,47 @@ impl<T: Texture> Material for Lambertian<T> {
         cosine = if cosine < 0.0 { 0.0 } else { cosine / PI };
         cosine
     }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let value = srec.attenuation * self.scattering_pdf(r_in, rec, scattered);
+        match self.clearcoat {
+            //light leaving the base is partly reflected back by the coat
+            Some(ior) => {
+                let cosine = Vec3::dot(&rec.normal, &Vec3::unit_vector(scattered.direct));
+                value * (1.0 - fresnel_dielectric(cosine, ior))
+            }
+            None => value,
+        }
+    }
 }
 
 impl Lambertian<SolidColor> {
     pub fn new(a: Color) -> Self {
         Self {
             albedo: SolidColor::new(&a),
+            clearcoat: None,
         }
     }
 }
 impl<T: Texture> Lambertian<T> {
     pub fn newp(a: T) -> Self {
-        Self { albedo: (a) }
+        Self {
+            albedo: (a),
+            clearcoat: None,
+        }
+    }
+
+    //glossy paint or lacquered wood, ior around 1.5
+    pub fn newp_coated(a: T, ior: f64) -> Self {
+        Self {
+            albedo: (a),
+            clearcoat: Some(ior),
+        }
     }
 }
@@ -3,6 +3,8 @@ use super::super::basic_tools::{
     vec3::{Color, Point, Vec3},
 };
 use super::super::hittable::{hittable_origin::HitRecord, pdf::PDF};
+use super::subsurface::SubsurfaceMedium;
+use super::thin_film::{Substrate, ThinFilm};
 use std::sync::Arc;
 #[derive(Default)]
 pub struct ScatterRecord {
@@ -10,6 +12,8 @@ pub struct ScatterRecord {
     pub is_specular: bool,
     pub attenuation: Color,
     pub pdf_ptr: Option<Arc<dyn PDF>>,
+    pub lobe_records: Vec<ScatterRecord>, //what each lobe of a blended material scattered
+    pub medium: Option<SubsurfaceMedium>, //the medium the specular ray travels through, none for air
 }
 pub trait Material: Send + Sync {
     fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _src: &mut ScatterRecord) -> bool {
@@ -20,9 +24,25 @@ pub trait Material: Send + Sync {
         0.0
     }
 
+    //brdf * cosine towards scattered, only used when the scatter is not specular
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        srec.attenuation * self.scattering_pdf(r_in, rec, scattered)
+    }
+
     fn emit(&self, _u: f64, _v: f64, _p: &Point, _r_in: &Ray, _rec: &HitRecord) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
+
+    //true if scatter depends on r_in.wavelength, the path then picks a single wavelength
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Clone, Copy, Default)]
@@ -51,6 +71,15 @@ impl ONB {
         self.u() * a.x + self.v() * a.y + self.w() * a.z
     }
 
+    //inverse of local_vec
+    pub fn to_local(&self, a: Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot(&a, &self.u()),
+            Vec3::dot(&a, &self.v()),
+            Vec3::dot(&a, &self.w()),
+        )
+    }
+
     pub fn build_from_w(&mut self, n: Vec3) {
         self.axis[2] = Vec3::unit_vector(n);
         let a = if (self.w().x).abs() > 0.9 {
@@ -67,6 +96,7 @@ impl ONB {
 pub struct Metal {
     pub albebo: Color,
     pub fuzz: f64,
+    pub film: Option<ThinFilm>,
 }
 
 impl Material for Metal {
@@ -77,7 +107,16 @@ impl Material for Metal {
             reflected + Vec3::random_in_unit_sphere() * self.fuzz,
             0.0,
         );
-        srec.attenuation = self.albebo;
+        srec.attenuation = match &self.film {
+            Some(film) => film.reflectance(
+                rec,
+                Vec3::dot(&-Vec3::unit_vector(r_in.direct), &rec.normal),
+                1.0,
+                &Substrate::Reflector(self.albebo),
+                r_in.wavelength,
+            ),
+            None => self.albebo,
+        };
         srec.is_specular = true;
         srec.pdf_ptr = None;
         true
@@ -93,6 +132,15 @@ impl Metal {
         Self {
             albebo: al,
             fuzz: fuzzz,
+            film: None,
+        }
+    }
+
+    //oil on steel or a beetle shell
+    pub fn new_film(al: Color, fuz: f64, film: ThinFilm) -> Self {
+        Self {
+            film: Some(film),
+            ..Self::new(al, fuz)
         }
     }
 }
@@ -0,0 +1,197 @@
+use super::super::basic_tools::vec3::{Color, Vec3};
+use std::f64::consts::PI;
+
+//GGX / Trowbridge-Reitz helpers, vectors are in the local frame with the normal along +z
+pub fn roughness_to_alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(1e-4)
+}
+
+pub fn distribution(wm: &Vec3, alpha: f64) -> f64 {
+    let cos2 = wm.z * wm.z;
+    if cos2 <= 0.0 {
+        return 0.0;
+    }
+    let tan2 = (1.0 - cos2) / cos2;
+    let e = 1.0 + tan2 / (alpha * alpha);
+    1.0 / (PI * alpha * alpha * cos2 * cos2 * e * e)
+}
+
+pub fn lambda(w: &Vec3, alpha: f64) -> f64 {
+    let cos2 = w.z * w.z;
+    if cos2 <= 0.0 {
+        return 0.0;
+    }
+    let tan2 = (1.0 - cos2) / cos2;
+    ((1.0 + alpha * alpha * tan2).sqrt() - 1.0) / 2.0
+}
+
+pub fn smith_g1(w: &Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(w, alpha))
+}
+
+pub fn smith_g(wo: &Vec3, wi: &Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(wo, alpha) + lambda(wi, alpha))
+}
+
+//density of the visible normal wm seen from wo
+pub fn visible_pdf(wo: &Vec3, wm: &Vec3, alpha: f64) -> f64 {
+    if wo.z == 0.0 {
+        return 0.0;
+    }
+    smith_g1(wo, alpha) * Vec3::dot(wo, wm).abs() * distribution(wm, alpha) / wo.z.abs()
+}
+
+//Heitz, "Sampling the GGX Distribution of Visible Normals"
+pub fn sample_visible_normal(wo: &Vec3, alpha: f64, u1: f64, u2: f64) -> Vec3 {
+    let flip = wo.z < 0.0;
+    let w = if flip { -*wo } else { *wo };
+    let vh = Vec3::unit_vector(Vec3::new(alpha * w.x, alpha * w.y, w.z));
+    let lensq = vh.x * vh.x + vh.y * vh.y;
+    let t1 = if lensq > 0.0 {
+        Vec3::new(-vh.y, vh.x, 0.0) / lensq.sqrt()
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let t2 = Vec3::cross(vh, t1);
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let p1 = r * phi.cos();
+    let s = 0.5 * (1.0 + vh.z);
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+    let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+    let wm = Vec3::unit_vector(Vec3::new(alpha * nh.x, alpha * nh.y, nh.z.max(1e-6)));
+    if flip {
+        -wm
+    } else {
+        wm
+    }
+}
+
+//Berry distribution (GTR with gamma = 1) used by the Disney clearcoat lobe
+pub fn gtr1_distribution(wm: &Vec3, alpha: f64) -> f64 {
+    if wm.z <= 0.0 {
+        return 0.0;
+    }
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * wm.z * wm.z))
+}
+
+//normal with density gtr1_distribution * cos
+pub fn sample_gtr1(alpha: f64, u1: f64, u2: f64) -> Vec3 {
+    let a2 = alpha * alpha;
+    let cos_theta = ((1.0 - a2.powf(1.0 - u1)) / (1.0 - a2)).max(0.0).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+pub fn schlick_weight(cos_theta: f64) -> f64 {
+    (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+fn fresnel_conductor_channel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta * cos_theta;
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+    let t0 = eta2 - k2 - sin2;
+    let a2plusb2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2plusb2 + cos2;
+    let a = (0.5 * (a2plusb2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2plusb2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+//unpolarized reflectance of a conductor with complex index eta + i k
+pub fn fresnel_conductor(cos_theta: f64, eta: &Color, k: &Color) -> Color {
+    let c = cos_theta.abs().min(1.0);
+    Color::new(
+        fresnel_conductor_channel(c, eta.x, k.x),
+        fresnel_conductor_channel(c, eta.y, k.y),
+        fresnel_conductor_channel(c, eta.z, k.z),
+    )
+}
+
+//unpolarized reflectance of a dielectric interface, eta = n_transmitted / n_incident
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta_i.abs().min(1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+//Walter et al., "Microfacet Models for Refraction through Rough Surfaces"
+//wo.z > 0 and eta is the relative index of the side wo does not lie on
+fn dielectric_half_vector(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+    let reflect = wi.z > 0.0;
+    let etap = if reflect { 1.0 } else { eta };
+    let mut wm = *wi * etap + *wo;
+    if wm.length_squared() == 0.0 {
+        return None;
+    }
+    wm = Vec3::unit_vector(wm);
+    if wm.z < 0.0 {
+        wm = -wm;
+    }
+    //discard back facing microfacets
+    if Vec3::dot(&wm, wi) * wi.z < 0.0 || Vec3::dot(&wm, wo) * wo.z < 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+//bsdf * |cos_i| without the 1 / eta^2 radiance scaling, matching the smooth Dielectric
+pub fn dielectric_bsdf_cos(wo: &Vec3, wi: &Vec3, alpha: f64, eta: f64) -> f64 {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return 0.0;
+    }
+    let wm = match dielectric_half_vector(wo, wi, eta) {
+        Some(wm) => wm,
+        None => return 0.0,
+    };
+    let fresnel = fresnel_dielectric(Vec3::dot(wo, &wm), eta);
+    let d = distribution(&wm, alpha);
+    let g = smith_g(wo, wi, alpha);
+    if wi.z > 0.0 {
+        return d * g * fresnel / (4.0 * wo.z);
+    }
+    let denom = Vec3::dot(wi, &wm) + Vec3::dot(wo, &wm) / eta;
+    d * g
+        * (1.0 - fresnel)
+        * (Vec3::dot(wi, &wm) * Vec3::dot(wo, &wm) / (wo.z * denom * denom)).abs()
+}
+
+pub fn dielectric_pdf(wo: &Vec3, wi: &Vec3, alpha: f64, eta: f64) -> f64 {
+    if wo.z <= 0.0 || wi.z == 0.0 {
+        return 0.0;
+    }
+    let wm = match dielectric_half_vector(wo, wi, eta) {
+        Some(wm) => wm,
+        None => return 0.0,
+    };
+    let fresnel = fresnel_dielectric(Vec3::dot(wo, &wm), eta);
+    if wi.z > 0.0 {
+        return fresnel * visible_pdf(wo, &wm, alpha) / (4.0 * Vec3::dot(wo, &wm).abs());
+    }
+    let denom = Vec3::dot(wi, &wm) + Vec3::dot(wo, &wm) / eta;
+    let dwm_dwi = Vec3::dot(wi, &wm).abs() / (denom * denom);
+    (1.0 - fresnel) * visible_pdf(wo, &wm, alpha) * dwm_dwi
+}
+
+pub fn sample_dielectric(wo: &Vec3, alpha: f64, eta: f64, u1: f64, u2: f64, uc: f64) -> Vec3 {
+    let wm = sample_visible_normal(wo, alpha, u1, u2);
+    let fresnel = fresnel_dielectric(Vec3::dot(wo, &wm), eta);
+    if uc < fresnel {
+        return Vec3::reflect(-*wo, wm);
+    }
+    Vec3::refract(-*wo, wm, 1.0 / eta)
+}
@@ -2,9 +2,11 @@ use super::super::basic_tools::{
     ray::Ray,
     vec3::{Color, Point},
 };
-use super::super::hittable::hittable_origin::{random_double, HitRecord};
+use super::super::hittable::hittable_origin::HitRecord;
+use super::blend::{blend_scatter, blend_scattering_value};
 use super::metal::{Material, ScatterRecord};
 
+//fixed rate blend, see BlendMaterial for a textured mask
 #[derive(Clone)]
 pub struct MixtureMaterial<M, N>
 where
@@ -26,26 +28,30 @@ impl<M: Material, N: Material> MixtureMaterial<M, N> {
 }
 impl<M: Material, N: Material> Material for MixtureMaterial<M, N> {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
-        let c = random_double();
-        if c < self.rate {
-            return self.m1.scatter(r_in, rec, srec);
-        }
-        self.m2.scatter(r_in, rec, srec)
+        blend_scatter([&self.m1, &self.m2], self.rate, r_in, rec, srec)
     }
 
     fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
-        let c = random_double();
-        if c < self.rate {
-            return self.m1.scattering_pdf(r_in, rec, scattered);
-        }
-        self.m2.scattering_pdf(r_in, rec, scattered)
+        self.m1.scattering_pdf(r_in, rec, scattered) * self.rate
+            + self.m2.scattering_pdf(r_in, rec, scattered) * (1.0 - self.rate)
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        blend_scattering_value([&self.m1, &self.m2], self.rate, r_in, rec, srec, scattered)
     }
 
     fn emit(&self, u: f64, v: f64, p: &Point, r_in: &Ray, rec: &HitRecord) -> Color {
-        let c = random_double();
-        if c < self.rate {
-            return self.m1.emit(u, v, p, r_in, rec);
-        }
-        self.m2.emit(u, v, p, r_in, rec)
+        self.m1.emit(u, v, p, r_in, rec) * self.rate
+            + self.m2.emit(u, v, p, r_in, rec) * (1.0 - self.rate)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.m1.is_dispersive() || self.m2.is_dispersive()
     }
 }
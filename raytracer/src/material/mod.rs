@@ -1,6 +1,14 @@
+pub mod blend;
+pub mod conductor;
 pub mod dielectric;
 pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
 pub mod mixmaterial;
+pub mod oren_nayar;
+pub mod principled;
+pub mod rough_dielectric;
+pub mod subsurface;
+pub mod thin_film;
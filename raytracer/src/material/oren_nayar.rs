@@ -0,0 +1,90 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::texture::text::{SolidColor, Texture};
+
+use super::super::basic_tools::{
+    camera::degrees_to_radians,
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::{hittable_origin::HitRecord, pdf::CosinePDF};
+use super::metal::{Material, ScatterRecord, ONB};
+
+//rough diffuse surface made of v-shaped lambertian facets, Oren and Nayar's qualitative model
+#[derive(Clone)]
+pub struct OrenNayar<T>
+where
+    T: Texture,
+{
+    pub albedo: T,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl<T: Texture> OrenNayar<T> {
+    //sigma is the standard deviation of the facet slopes in degrees, 0 is lambertian
+    pub fn newp(albedo: T, sigma: f64) -> Self {
+        let sigma = degrees_to_radians(sigma);
+        let sigma2 = sigma * sigma;
+        Self {
+            albedo: (albedo),
+            a: (1.0 - sigma2 / (2.0 * (sigma2 + 0.33))),
+            b: (0.45 * sigma2 / (sigma2 + 0.09)),
+        }
+    }
+}
+
+impl OrenNayar<SolidColor> {
+    pub fn new(a: Color, sigma: f64) -> Self {
+        Self::newp(SolidColor::new(&a), sigma)
+    }
+}
+
+impl<T: Texture> Material for OrenNayar<T> {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = false;
+        srec.attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        srec.pdf_ptr = Some(Arc::new(CosinePDF::new(rec.normal)));
+        true
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cosine = Vec3::dot(&rec.normal, &Vec3::unit_vector(scattered.direct));
+        if cosine < 0.0 {
+            0.0
+        } else {
+            cosine / PI
+        }
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(rec.normal);
+        let wo = Vec3::unit_vector(uvw.to_local(-r_in.direct));
+        let wi = Vec3::unit_vector(uvw.to_local(scattered.direct));
+        if wi.z <= 0.0 || wo.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+        let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+
+        //cos(phi_i - phi_o) from the projections onto the tangent plane
+        let mut max_cos = 0.0;
+        if sin_i > 1e-4 && sin_o > 1e-4 {
+            max_cos = ((wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o)).max(0.0);
+        }
+        //alpha is the larger of the two polar angles, beta the smaller
+        let (sin_alpha, tan_beta) = if wi.z > wo.z {
+            (sin_o, sin_i / wi.z)
+        } else {
+            (sin_i, sin_o / wo.z)
+        };
+        srec.attenuation * ((self.a + self.b * max_cos * sin_alpha * tan_beta) * wi.z / PI)
+    }
+}
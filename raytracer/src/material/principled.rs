@@ -0,0 +1,233 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::{
+    hittable_origin::{clamp, HitRecord},
+    pdf::{ClearcoatPDF, CosinePDF, RoughDielectricPDF, WeightedPDF, GGXPDF, PDF},
+};
+use super::metal::{Material, ScatterRecord, ONB};
+use super::microfacet::{
+    dielectric_bsdf_cos, distribution, gtr1_distribution, roughness_to_alpha, schlick_weight,
+    smith_g,
+};
+use crate::texture::text::{DynTexture, SolidColor, Texture};
+
+//every parameter is a texture, scalars read the first channel
+#[derive(Clone)]
+pub struct PrincipledParams {
+    pub metallic: DynTexture,
+    pub roughness: DynTexture,
+    pub specular: DynTexture, //0.5 is a 4% reflectance at normal incidence
+    pub specular_tint: DynTexture,
+    pub sheen: DynTexture,
+    pub sheen_tint: DynTexture,
+    pub clearcoat: DynTexture,
+    pub clearcoat_gloss: DynTexture,
+    pub transmission: DynTexture,
+    pub ior: DynTexture,
+}
+
+impl Default for PrincipledParams {
+    fn default() -> Self {
+        Self {
+            metallic: DynTexture::constant(0.0),
+            roughness: DynTexture::constant(0.5),
+            specular: DynTexture::constant(0.5),
+            specular_tint: DynTexture::constant(0.0),
+            sheen: DynTexture::constant(0.0),
+            sheen_tint: DynTexture::constant(0.5),
+            clearcoat: DynTexture::constant(0.0),
+            clearcoat_gloss: DynTexture::constant(1.0),
+            transmission: DynTexture::constant(0.0),
+            ior: DynTexture::constant(1.5),
+        }
+    }
+}
+
+//parameters looked up at one hit point
+struct Lobes {
+    base_color: Color,
+    roughness: f64,
+    alpha: f64,
+    specular_color: Color,
+    sheen: Color,
+    clearcoat: f64,
+    clearcoat_alpha: f64,
+    eta: f64,
+    diffuse_weight: f64,
+    specular_weight: f64,
+    transmission_weight: f64,
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+//Burley, "Physically Based Shading at Disney", with the 2015 transmission extension
+#[derive(Clone)]
+pub struct Principled<T>
+where
+    T: Texture,
+{
+    pub base_color: T,
+    pub params: PrincipledParams,
+}
+
+impl<T: Texture> Principled<T> {
+    pub fn new(base_color: T, params: PrincipledParams) -> Self {
+        Self {
+            base_color: (base_color),
+            params: (params),
+        }
+    }
+
+    fn lobes(&self, rec: &HitRecord) -> Lobes {
+        let (u, v, p) = (rec.u, rec.v, &rec.p);
+        let base_color = self.base_color.value(u, v, p);
+        let metallic = clamp(self.params.metallic.scalar(u, v, p), 0.0, 1.0);
+        let roughness = clamp(self.params.roughness.scalar(u, v, p), 0.0, 1.0);
+        let transmission = clamp(self.params.transmission.scalar(u, v, p), 0.0, 1.0);
+        let ior = self.params.ior.scalar(u, v, p).max(1.01);
+
+        let white = Color::new(1.0, 1.0, 1.0);
+        let lum = base_color.luminance();
+        let tint = if lum > 0.0 { base_color / lum } else { white };
+        let specular_color = lerp(
+            lerp(white, tint, self.params.specular_tint.scalar(u, v, p))
+                * (0.08 * self.params.specular.scalar(u, v, p)),
+            base_color,
+            metallic,
+        );
+        let sheen = lerp(white, tint, self.params.sheen_tint.scalar(u, v, p))
+            * self.params.sheen.scalar(u, v, p);
+        let clearcoat_gloss = self.params.clearcoat_gloss.scalar(u, v, p);
+        Lobes {
+            base_color: (base_color),
+            roughness: (roughness),
+            alpha: (roughness_to_alpha(roughness)),
+            specular_color: (specular_color),
+            sheen: (sheen),
+            clearcoat: (self.params.clearcoat.scalar(u, v, p).max(0.0)),
+            clearcoat_alpha: (0.1 * (1.0 - clearcoat_gloss) + 0.001 * clearcoat_gloss),
+            eta: (if rec.front_face { ior } else { 1.0 / ior }),
+            diffuse_weight: ((1.0 - metallic) * (1.0 - transmission)),
+            specular_weight: (1.0 - (1.0 - metallic) * transmission),
+            transmission_weight: ((1.0 - metallic) * transmission),
+        }
+    }
+
+    fn local_directions(r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(rec.normal);
+        let wo = Vec3::unit_vector(uvw.to_local(-r_in.direct));
+        let wi = Vec3::unit_vector(uvw.to_local(scattered.direct));
+        (wo, wi)
+    }
+
+    fn sampling_pdf(&self, r_in: &Ray, rec: &HitRecord, lobes: &Lobes) -> Option<WeightedPDF> {
+        let wo = -r_in.direct;
+        let cos_o = Vec3::dot(&Vec3::unit_vector(wo), &rec.normal);
+        let fresnel = schlick_weight(cos_o);
+        let spec = lobes.specular_color * (1.0 - fresnel) + Color::new(1.0, 1.0, 1.0) * fresnel;
+        let weights = vec![
+            lobes.diffuse_weight * lobes.base_color.luminance().max(0.05),
+            lobes.specular_weight * spec.luminance(),
+            0.25 * lobes.clearcoat * (0.04 + 0.96 * fresnel),
+            lobes.transmission_weight * lobes.base_color.luminance().max(0.05),
+        ];
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return None;
+        }
+        let pdfs: Vec<Arc<dyn PDF>> = vec![
+            Arc::new(CosinePDF::new(rec.normal)),
+            Arc::new(GGXPDF::new(rec.normal, wo, lobes.alpha)),
+            Arc::new(ClearcoatPDF::new(rec.normal, wo, lobes.clearcoat_alpha)),
+            Arc::new(RoughDielectricPDF::new(
+                rec.normal,
+                wo,
+                lobes.alpha,
+                lobes.eta,
+            )),
+        ];
+        Some(WeightedPDF::new(pdfs, weights))
+    }
+}
+
+impl Principled<SolidColor> {
+    pub fn new_col(c: Color, params: PrincipledParams) -> Self {
+        Self::new(SolidColor::new(&c), params)
+    }
+}
+
+impl<T: Texture> Material for Principled<T> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        let lobes = self.lobes(rec);
+        let pdf = match self.sampling_pdf(r_in, rec, &lobes) {
+            Some(pdf) => pdf,
+            None => return false,
+        };
+        srec.is_specular = false;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        srec.pdf_ptr = Some(Arc::new(pdf));
+        true
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let lobes = self.lobes(rec);
+        match self.sampling_pdf(r_in, rec, &lobes) {
+            Some(pdf) => pdf.value(&scattered.direct),
+            None => 0.0,
+        }
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        _srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let l = self.lobes(rec);
+        let (wo, wi) = Principled::<T>::local_directions(r_in, rec, scattered);
+        if wo.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let mut value = Color::new(0.0, 0.0, 0.0);
+        if l.transmission_weight > 0.0 {
+            value += l.base_color
+                * (l.transmission_weight * dielectric_bsdf_cos(&wo, &wi, l.alpha, l.eta));
+        }
+        if wi.z <= 0.0 {
+            return value;
+        }
+        let wm = Vec3::unit_vector(wo + wi);
+        let cos_d = Vec3::dot(&wi, &wm);
+        let fd = schlick_weight(cos_d);
+
+        //retro-reflective diffuse plus sheen at grazing angles
+        let fd90 = 0.5 + 2.0 * l.roughness * cos_d * cos_d;
+        let retro = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z))
+            * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z));
+        value += (l.base_color * (retro / PI) + l.sheen * fd) * (l.diffuse_weight * wi.z);
+
+        let fresnel = l.specular_color * (1.0 - fd) + Color::new(1.0, 1.0, 1.0) * fd;
+        value += fresnel
+            * (l.specular_weight * distribution(&wm, l.alpha) * smith_g(&wo, &wi, l.alpha)
+                / (4.0 * wo.z));
+
+        if l.clearcoat > 0.0 {
+            let fr = 0.04 + 0.96 * fd;
+            let coat = 0.25
+                * l.clearcoat
+                * fr
+                * gtr1_distribution(&wm, l.clearcoat_alpha)
+                * smith_g(&wo, &wi, 0.25)
+                / (4.0 * wo.z);
+            value += Color::new(coat, coat, coat);
+        }
+        value
+    }
+}
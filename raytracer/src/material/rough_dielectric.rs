@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::{
+    hittable_origin::HitRecord,
+    pdf::{RoughDielectricPDF, PDF},
+};
+use super::dielectric::Dielectric;
+use super::metal::{Material, ScatterRecord, ONB};
+use super::microfacet::{dielectric_bsdf_cos, roughness_to_alpha};
+
+//frosted glass, GGX microfacet reflection and transmission
+#[derive(Clone)]
+pub struct RoughDielectric {
+    pub ir: f64,
+    pub roughness: f64,
+    pub alpha: f64,
+    pub absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_ref: f64, roughness: f64) -> Self {
+        Self::new_absorbing(index_of_ref, roughness, Color::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn new_absorbing(index_of_ref: f64, roughness: f64, absorption: Color) -> Self {
+        Self {
+            ir: (index_of_ref),
+            roughness: (roughness),
+            alpha: (roughness_to_alpha(roughness)),
+            absorption: (absorption),
+        }
+    }
+
+    fn is_smooth(&self) -> bool {
+        self.roughness < 0.01
+    }
+
+    //relative index across the interface as seen from the incoming ray
+    fn eta(&self, rec: &HitRecord) -> f64 {
+        if rec.front_face {
+            self.ir
+        } else {
+            1.0 / self.ir
+        }
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        if self.is_smooth() {
+            return Dielectric::new_absorbing(self.ir, self.absorption).scatter(r_in, rec, srec);
+        }
+        srec.is_specular = false;
+        srec.attenuation = Dielectric::transmittance(&self.absorption, r_in, rec);
+        srec.pdf_ptr = Some(Arc::new(RoughDielectricPDF::new(
+            rec.normal,
+            -r_in.direct,
+            self.alpha,
+            self.eta(rec),
+        )));
+        true
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        RoughDielectricPDF::new(rec.normal, -r_in.direct, self.alpha, self.eta(rec))
+            .value(&scattered.direct)
+    }
+
+    fn scattering_value(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        srec: &ScatterRecord,
+        scattered: &Ray,
+    ) -> Color {
+        let mut uvw = ONB::default();
+        uvw.build_from_w(rec.normal);
+        let wo = Vec3::unit_vector(uvw.to_local(-r_in.direct));
+        let wi = Vec3::unit_vector(uvw.to_local(scattered.direct));
+        let value = dielectric_bsdf_cos(&wo, &wi, self.alpha, self.eta(rec));
+        srec.attenuation * value
+    }
+}
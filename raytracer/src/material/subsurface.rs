@@ -0,0 +1,238 @@
+use super::super::basic_tools::{
+    ray::Ray,
+    vec3::{Color, Vec3},
+};
+use super::super::hittable::hittable_origin::{clamp, random_double, HitRecord};
+use super::dielectric::{min, Dielectric};
+use super::metal::{Material, ScatterRecord, ONB};
+use std::f64::{consts::PI, INFINITY};
+
+//longest random walk inside a slab before the path is dropped
+const MAX_SLAB_EVENTS: usize = 256;
+
+fn channel(c: &Color, i: usize) -> f64 {
+    match i {
+        0 => c.x,
+        1 => c.y,
+        _ => c.z,
+    }
+}
+
+fn exp_color(c: Color) -> Color {
+    Color::new(c.x.exp(), c.y.exp(), c.z.exp())
+}
+
+//homogeneous scattering medium filling the inside of an object, coefficients per unit length
+#[derive(Clone, Copy, Default)]
+pub struct SubsurfaceMedium {
+    pub sigma_a: Color,
+    pub sigma_s: Color,
+    pub g: f64, //Henyey-Greenstein anisotropy, > 0 scatters forward
+}
+
+impl SubsurfaceMedium {
+    pub fn new(sigma_a: Color, sigma_s: Color, g: f64) -> Self {
+        Self {
+            sigma_a: (sigma_a),
+            sigma_s: (sigma_s),
+            g: (g),
+        }
+    }
+
+    pub fn sigma_t(&self) -> Color {
+        self.sigma_a + self.sigma_s
+    }
+
+    //free flight like ConstantMedium, with the channel to sample picked at random;
+    //returns the distance of a scattering event before t_max and the path weight
+    pub fn sample_distance(&self, t_max: f64, weight: &mut Color) -> Option<f64> {
+        let sigma_t = self.sigma_t();
+        let c = ((random_double() * 3.0) as usize).min(2);
+        let st = channel(&sigma_t, c);
+        let mut t = if st > 0.0 {
+            -(1.0 - random_double()).ln() / st
+        } else {
+            INFINITY
+        };
+        let scattered = t < t_max;
+        if !scattered {
+            t = t_max;
+        }
+        let tr = if t.is_finite() {
+            exp_color(sigma_t * -t)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        };
+        let density = if scattered { sigma_t * tr } else { tr };
+        let pdf = (density.x + density.y + density.z) / 3.0;
+        if pdf <= 0.0 {
+            *weight = Color::new(0.0, 0.0, 0.0);
+            return None;
+        }
+        if scattered {
+            *weight = tr * self.sigma_s / pdf;
+            Some(t)
+        } else {
+            *weight = tr / pdf;
+            None
+        }
+    }
+
+    //new direction around the old one, drawn from the Henyey-Greenstein phase function
+    pub fn sample_phase(&self, direction: &Vec3) -> Vec3 {
+        let g = self.g;
+        let u = random_double();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        };
+        let cos_theta = clamp(cos_theta, -1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_double();
+        let mut uvw = ONB::default();
+        uvw.build_from_w(*direction);
+        uvw.local(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+}
+
+//translucent object, light refracts in and random walks through the medium inside;
+//closed objects walk through the scene geometry, open ones like rings walk inside a slab
+#[derive(Clone)]
+pub struct Subsurface {
+    pub ir: f64,
+    pub medium: SubsurfaceMedium,
+    pub slab_thickness: Option<f64>,
+}
+
+impl Subsurface {
+    pub fn new(index_of_ref: f64, sigma_a: Color, sigma_s: Color, g: f64) -> Self {
+        Self {
+            ir: (index_of_ref),
+            medium: (SubsurfaceMedium::new(sigma_a, sigma_s, g)),
+            slab_thickness: None,
+        }
+    }
+
+    //color after many bounces is roughly albedo, mean_free_path is the average flight length
+    pub fn new_albedo(index_of_ref: f64, albedo: Color, mean_free_path: f64, g: f64) -> Self {
+        let sigma_t = 1.0 / mean_free_path;
+        let white = Color::new(1.0, 1.0, 1.0);
+        Self::new(
+            index_of_ref,
+            (white - albedo) * sigma_t,
+            albedo * sigma_t,
+            g,
+        )
+    }
+
+    pub fn new_slab(
+        index_of_ref: f64,
+        albedo: Color,
+        mean_free_path: f64,
+        g: f64,
+        thickness: f64,
+    ) -> Self {
+        Self {
+            slab_thickness: Some(thickness),
+            ..Self::new_albedo(index_of_ref, albedo, mean_free_path, g)
+        }
+    }
+
+    pub fn skin(mean_free_path: f64) -> Self {
+        Self::new_albedo(1.4, Color::new(0.9, 0.6, 0.45), mean_free_path, 0.8)
+    }
+
+    pub fn wax(mean_free_path: f64) -> Self {
+        Self::new_albedo(1.45, Color::new(0.95, 0.9, 0.75), mean_free_path, 0.3)
+    }
+
+    pub fn jade(mean_free_path: f64) -> Self {
+        Self::new_albedo(1.66, Color::new(0.55, 0.9, 0.6), mean_free_path, 0.5)
+    }
+
+    //reflect or refract at the surface, n faces the incoming direction
+    fn cross_interface(&self, unit_direction: Vec3, n: Vec3, ratio: f64) -> (Vec3, bool) {
+        let cos_theta = min(Vec3::dot(&-unit_direction, &n), 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        if ratio * sin_theta > 1.0 || Dielectric::reflectance(cos_theta, ratio) > random_double() {
+            return (Vec3::reflect(unit_direction, n), false);
+        }
+        (Vec3::refract(unit_direction, n, ratio), true)
+    }
+
+    //random walk through a slab under the hit point, depth measured along -normal
+    fn walk_slab(&self, direction: Vec3, rec: &HitRecord, thickness: f64) -> Option<(Vec3, Color)> {
+        let axis = -rec.normal;
+        let mut d = direction;
+        let mut z = 0.0;
+        let mut weight = Color::new(1.0, 1.0, 1.0);
+        for _i in 0..MAX_SLAB_EVENTS {
+            let dz = Vec3::dot(&d, &axis);
+            let t_max = if dz > 0.0 {
+                (thickness - z) / dz
+            } else if dz < 0.0 {
+                -z / dz
+            } else {
+                INFINITY
+            };
+            let mut w = Color::new(1.0, 1.0, 1.0);
+            let event = self.medium.sample_distance(t_max, &mut w);
+            weight = weight * w;
+            if let Some(t) = event {
+                z += dz * t;
+                d = self.medium.sample_phase(&d);
+                continue;
+            }
+            if !t_max.is_finite() {
+                return None;
+            }
+            z = if dz > 0.0 { thickness } else { 0.0 };
+            let n = if dz > 0.0 { -axis } else { axis };
+            let (next, refracted) = self.cross_interface(d, n, self.ir);
+            if refracted {
+                return Some((next, weight));
+            }
+            d = next;
+        }
+        None
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, srec: &mut ScatterRecord) -> bool {
+        srec.is_specular = true;
+        srec.pdf_ptr = None;
+        srec.attenuation = Color::new(1.0, 1.0, 1.0);
+        let unit_direction = Vec3::unit_vector(r_in.direct);
+        let ratio = if rec.front_face {
+            1.0 / self.ir
+        } else {
+            self.ir
+        };
+        let (direction, refracted) = self.cross_interface(unit_direction, rec.normal, ratio);
+
+        if let Some(thickness) = self.slab_thickness {
+            if !refracted {
+                srec.specular_ray = Ray::new(rec.p, direction, r_in.time);
+                return true;
+            }
+            return match self.walk_slab(direction, rec, thickness) {
+                Some((exit, weight)) => {
+                    srec.attenuation = weight;
+                    srec.specular_ray = Ray::new(rec.p, exit, r_in.time);
+                    true
+                }
+                None => false,
+            };
+        }
+
+        //entering, or reflected back while inside, keeps the ray in the medium
+        srec.specular_ray = Ray::new(rec.p, direction, r_in.time);
+        if refracted == rec.front_face {
+            srec.medium = Some(self.medium);
+        }
+        true
+    }
+}
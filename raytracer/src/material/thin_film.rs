@@ -0,0 +1,126 @@
+use super::super::basic_tools::{
+    spectrum::{wavelength_weight, LAMBDA_MAX, LAMBDA_MIN},
+    vec3::Color,
+};
+use super::super::hittable::hittable_origin::{clamp, HitRecord};
+use crate::texture::text::DynTexture;
+use std::f64::consts::PI;
+
+//wavelengths used to turn the film reflectance into rgb
+const FILM_SAMPLES: usize = 16;
+
+//what lies under the film
+#[derive(Clone, Copy)]
+pub enum Substrate {
+    Dielectric(f64),  //index of refraction
+    Reflector(Color), //a metal given by its rgb reflectance
+}
+
+impl Substrate {
+    fn albedo_at(color: &Color, lambda: f64) -> f64 {
+        if lambda < 490.0 {
+            color.z
+        } else if lambda < 580.0 {
+            color.y
+        } else {
+            color.x
+        }
+    }
+}
+
+//reflectance of one polarization given the amplitudes of the two interfaces (Airy summation)
+fn airy(r12: f64, r23: f64, cos_delta: f64) -> f64 {
+    let num = r12 * r12 + r23 * r23 + 2.0 * r12 * r23 * cos_delta;
+    let den = 1.0 + r12 * r12 * r23 * r23 + 2.0 * r12 * r23 * cos_delta;
+    clamp(num / den, 0.0, 1.0)
+}
+
+//a transparent layer a few hundred nm thick whose reflections interfere, soap or oil on water
+#[derive(Clone)]
+pub struct ThinFilm {
+    pub ior: f64,
+    pub thickness: DynTexture, //nm, first channel
+}
+
+impl ThinFilm {
+    pub fn new(ior: f64, thickness: f64) -> Self {
+        Self::new_textured(ior, DynTexture::constant(thickness))
+    }
+
+    pub fn new_textured(ior: f64, thickness: DynTexture) -> Self {
+        Self {
+            ior: (ior),
+            thickness: (thickness),
+        }
+    }
+
+    //outside is the index on the side of the incoming light
+    fn reflectance_at(
+        &self,
+        lambda: f64,
+        thickness: f64,
+        cos_i: f64,
+        outside: f64,
+        substrate: &Substrate,
+    ) -> f64 {
+        let (n1, n2) = (outside, self.ior);
+        let cos1 = cos_i.abs().min(1.0);
+        let sin1_2 = 1.0 - cos1 * cos1;
+        let sin2_2 = sin1_2 * (n1 / n2).powi(2);
+        if sin2_2 >= 1.0 {
+            return 1.0;
+        }
+        let cos2 = (1.0 - sin2_2).sqrt();
+        let r12s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+        let r12p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+        let (r23s, r23p) = match substrate {
+            Substrate::Dielectric(n3) => {
+                let sin3_2 = sin1_2 * (n1 / n3).powi(2);
+                if sin3_2 >= 1.0 {
+                    (1.0, 1.0)
+                } else {
+                    let cos3 = (1.0 - sin3_2).sqrt();
+                    (
+                        (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3),
+                        (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3),
+                    )
+                }
+            }
+            //metals reflect with roughly half a wave of phase shift
+            Substrate::Reflector(color) => {
+                let r = -Substrate::albedo_at(color, lambda).max(0.0).sqrt();
+                (r, r)
+            }
+        };
+        let cos_delta = (4.0 * PI * n2 * thickness * cos2 / lambda).cos();
+        (airy(r12s, r23s, cos_delta) + airy(r12p, r23p, cos_delta)) / 2.0
+    }
+
+    //lambda > 0 is a path that already carries a single wavelength
+    pub fn reflectance(
+        &self,
+        rec: &HitRecord,
+        cos_i: f64,
+        outside: f64,
+        substrate: &Substrate,
+        lambda: f64,
+    ) -> Color {
+        let thickness = self.thickness.scalar(rec.u, rec.v, &rec.p).max(0.0);
+        if lambda > 0.0 {
+            let r = self.reflectance_at(lambda, thickness, cos_i, outside, substrate);
+            return Color::new(r, r, r);
+        }
+        let mut col = Color::new(0.0, 0.0, 0.0);
+        for i in 0..FILM_SAMPLES {
+            let l = LAMBDA_MIN + (i as f64 + 0.5) / FILM_SAMPLES as f64 * (LAMBDA_MAX - LAMBDA_MIN);
+            col +=
+                wavelength_weight(l) * self.reflectance_at(l, thickness, cos_i, outside, substrate);
+        }
+        col = col / FILM_SAMPLES as f64;
+        Color::new(
+            clamp(col.x, 0.0, 1.0),
+            clamp(col.y, 0.0, 1.0),
+            clamp(col.z, 0.0, 1.0),
+        )
+    }
+}
@@ -7,6 +7,45 @@ pub trait Texture: Send + Sync + Clone {
     fn value(&self, u: f64, v: f64, p: &Point) -> Color;
 }
 
+//object safe half of Texture, lets textures of different types share one field type
+pub trait TextureValue: Send + Sync {
+    fn dyn_value(&self, u: f64, v: f64, p: &Point) -> Color;
+}
+
+impl<T: Texture> TextureValue for T {
+    fn dyn_value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.value(u, v, p)
+    }
+}
+
+#[derive(Clone)]
+pub struct DynTexture {
+    pub texture: Arc<dyn TextureValue>,
+}
+
+impl DynTexture {
+    pub fn new<T: Texture + 'static>(texture: T) -> Self {
+        Self {
+            texture: (Arc::new(texture)),
+        }
+    }
+
+    pub fn constant(value: f64) -> Self {
+        Self::new(SolidColor::new(&Color::new(value, value, value)))
+    }
+
+    //scalar parameters read the first channel
+    pub fn scalar(&self, u: f64, v: f64, p: &Point) -> f64 {
+        self.texture.dyn_value(u, v, p).x
+    }
+}
+
+impl Texture for DynTexture {
+    fn value(&self, u: f64, v: f64, p: &Point) -> Color {
+        self.texture.dyn_value(u, v, p)
+    }
+}
+
 #[derive(Default, Clone, Copy)]
 pub struct SolidColor {
     pub color_value: Color,